@@ -0,0 +1,146 @@
+//! Adding and removing admins of a V4 config. Every change is signed by an
+//! owner over the config it applies to, so a change can't be replayed
+//! against another HoloPort or a later state of the same config.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use failure::{bail, Error};
+use serde::*;
+
+use crate::config::{
+    public_key_from_base64, signature_from_base64, signature_to_base64, to_base64, AdminIdentity,
+    AdminRole, AdminSettings,
+};
+use crate::Config;
+
+const SIGNATURE_CONTEXT: &[u8] = b"hpos-config admin change v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminChange {
+    Add(AdminIdentity),
+    Remove {
+        #[serde(
+            deserialize_with = "public_key_from_base64",
+            serialize_with = "to_base64"
+        )]
+        public_key: VerifyingKey,
+    },
+}
+
+/// An `AdminChange` with the signature of the owner authorizing it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SignedAdminChange {
+    pub change: AdminChange,
+    #[serde(
+        deserialize_with = "public_key_from_base64",
+        serialize_with = "to_base64"
+    )]
+    pub signer: VerifyingKey,
+    #[serde(
+        deserialize_with = "signature_from_base64",
+        serialize_with = "signature_to_base64"
+    )]
+    pub signature: Signature,
+}
+
+impl AdminChange {
+    /// Sign this change for `config` with an owner's admin keypair, see
+    /// `admin_keypair_from`
+    pub fn sign(self, config: &Config, owner: &SigningKey) -> Result<SignedAdminChange, Error> {
+        let signature = owner.sign(&signed_message(config, &self)?);
+        Ok(SignedAdminChange {
+            change: self,
+            signer: owner.verifying_key(),
+            signature,
+        })
+    }
+}
+
+/// binds the change to the exact config it is applied to
+fn signed_message(config: &Config, change: &AdminChange) -> Result<Vec<u8>, Error> {
+    let mut message = SIGNATURE_CONTEXT.to_vec();
+    message.extend(config.fingerprint()?.as_bytes());
+    message.extend(serde_json::to_vec(change)?);
+    Ok(message)
+}
+
+impl Config {
+    /// Turn a V3 config into a V4 config with its admin as the only owner
+    pub fn into_v4(self) -> Result<Config, Error> {
+        match self {
+            Config::V3 {
+                device_bundle,
+                device_derivation_path,
+                revocation_pub_key,
+                holoport_id,
+                initial_host_pub_key,
+                registration_code,
+                settings,
+            } => Ok(Config::V4 {
+                device_bundle,
+                device_derivation_path,
+                revocation_pub_key,
+                holoport_id,
+                initial_host_pub_key,
+                registration_code,
+                settings: AdminSettings {
+                    admins: vec![AdminIdentity {
+                        email: settings.admin.email,
+                        public_key: settings.admin.public_key,
+                        role: AdminRole::Owner,
+                        label: None,
+                    }],
+                    hosting_preferences: settings.hosting_preferences,
+                    network: settings.network,
+                },
+            }),
+            config @ Config::V4 { .. } => Ok(config),
+            _ => bail!("only V3 configs can be upgraded to V4"),
+        }
+    }
+
+    /// Apply a change signed by one of the owners of this V4 config
+    pub fn apply_admin_change(&mut self, signed: &SignedAdminChange) -> Result<(), Error> {
+        let owner = self.admin_by_public_key(&signed.signer);
+        if owner.map(|admin| admin.role) != Some(AdminRole::Owner) {
+            bail!("admin changes must be signed by an owner");
+        }
+        signed
+            .signer
+            .verify_strict(&signed_message(self, &signed.change)?, &signed.signature)?;
+
+        let admins = match self {
+            Config::V4 { settings, .. } => &mut settings.admins,
+            _ => bail!("only V4 configs support multiple admins, see `Config::into_v4`"),
+        };
+        match &signed.change {
+            AdminChange::Add(admin) => {
+                if admins
+                    .iter()
+                    .any(|existing| existing.public_key == admin.public_key)
+                {
+                    bail!("admin {} already exists", admin.email);
+                }
+                admins.push(admin.clone());
+            }
+            AdminChange::Remove { public_key } => {
+                let index = match admins
+                    .iter()
+                    .position(|admin| &admin.public_key == public_key)
+                {
+                    Some(index) => index,
+                    None => bail!("no admin with this public key"),
+                };
+                let owners = admins
+                    .iter()
+                    .filter(|admin| admin.role == AdminRole::Owner)
+                    .count();
+                if admins[index].role == AdminRole::Owner && owners == 1 {
+                    bail!("can not remove the last owner");
+                }
+                admins.remove(index);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! Holochain conductor config and lair seed import for a HoloPort, rendered
+//! from the templates in `core/templates` so HPOS doesn't assemble them with
+//! ad-hoc scripts.
+
+use failure::{bail, Error};
+use std::path::PathBuf;
+use url::Url;
+
+use crate::{public_key, Config};
+
+const CONDUCTOR_CONFIG_TEMPLATE: &str = include_str!("../templates/conductor-config.yaml");
+const LAIR_IMPORT_TEMPLATE: &str = include_str!("../templates/lair-import.sh");
+
+pub const DEFAULT_BOOTSTRAP_URL: &str = "https://bootstrap.holo.host";
+pub const DEFAULT_SIGNAL_URL: &str = "wss://signal.holo.host";
+
+/// Paths and ports of the HPOS installation the files are rendered for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConductorOptions {
+    pub data_root_path: PathBuf,
+    pub keystore_path: PathBuf,
+    pub admin_port: u16,
+    /// Lair tag the device bundle is imported under
+    pub seed_tag: String,
+}
+
+impl Default for ConductorOptions {
+    fn default() -> Self {
+        ConductorOptions {
+            data_root_path: "/var/lib/holochain-rsm/databases".into(),
+            keystore_path: "/var/lib/holochain-rsm/lair-keystore".into(),
+            admin_port: 4444,
+            seed_tag: "hpos-host".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConductorSetup {
+    /// Conductor config YAML
+    pub conductor_config: String,
+    /// Shell script importing the device bundle into lair
+    pub lair_import: String,
+}
+
+impl Config {
+    /// Render the conductor config and lair seed import for this config.
+    /// The device bundle is imported as is, its passphrase is never written
+    /// to either file.
+    pub fn conductor_setup(&self, options: &ConductorOptions) -> Result<ConductorSetup, Error> {
+        let (device_bundle, holoport_id, initial_host_pub_key) = match self {
+            Config::V3 {
+                device_bundle,
+                holoport_id,
+                initial_host_pub_key,
+                ..
+            }
+            | Config::V4 {
+                device_bundle,
+                holoport_id,
+                initial_host_pub_key,
+                ..
+            } => (device_bundle, holoport_id, initial_host_pub_key),
+            _ => bail!("only V3 and V4 configs carry the holoport agent key"),
+        };
+        // written unquoted into comments, so only use the canonical encoding
+        let holoport_key = public_key::from_base36_id(holoport_id)?;
+        let agent_key = public_key::to_holochain_encoded_agent_key(&holoport_key);
+        if agent_key != *initial_host_pub_key {
+            bail!("agent key does not belong to holoport {}", holoport_id);
+        }
+
+        let network = self.network();
+        let bootstrap_url = network
+            .and_then(|network| network.bootstrap_url.as_ref())
+            .map_or_else(|| DEFAULT_BOOTSTRAP_URL.to_string(), Url::to_string);
+        let signal_url = network
+            .and_then(|network| network.signal_url.as_ref())
+            .map_or_else(|| DEFAULT_SIGNAL_URL.to_string(), Url::to_string);
+        let data_root_path = options.data_root_path.to_string_lossy();
+        let keystore_path = options.keystore_path.to_string_lossy();
+        let admin_port = options.admin_port.to_string();
+
+        let conductor_config = render(
+            CONDUCTOR_CONFIG_TEMPLATE,
+            &[
+                ("agent_key", agent_key.clone()),
+                ("data_root_path", yaml_string(&data_root_path)),
+                ("keystore_path", yaml_string(&keystore_path)),
+                ("admin_port", admin_port),
+                ("bootstrap_url", yaml_string(&bootstrap_url)),
+                ("signal_url", yaml_string(&signal_url)),
+            ],
+        )?;
+        let lair_import = render(
+            LAIR_IMPORT_TEMPLATE,
+            &[
+                ("agent_key", agent_key),
+                ("keystore_path", shell_string(&keystore_path)),
+                ("seed_tag", shell_string(&options.seed_tag)),
+                ("device_bundle", shell_string(device_bundle)),
+            ],
+        )?;
+        Ok(ConductorSetup {
+            conductor_config,
+            lair_import,
+        })
+    }
+}
+
+/// replace every `{{name}}` in `template`, failing on names without a value
+fn render(template: &str, values: &[(&str, String)]) -> Result<String, Error> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => bail!("unterminated placeholder in template"),
+        };
+        let name = &rest[start + 2..end];
+        let value = match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => value,
+            None => bail!("no value for template placeholder {}", name),
+        };
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(value);
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// JSON strings are valid double quoted YAML scalars
+fn yaml_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+fn shell_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
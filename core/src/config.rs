@@ -1,37 +1,40 @@
-use arrayref::array_ref;
-use ed25519_dalek::{Digest, Sha512, SigningKey, VerifyingKey};
+use ed25519_dalek::{Digest, Sha512, Signature, SigningKey, VerifyingKey};
 use failure::Error;
 use serde::*;
+use std::fmt;
+use std::ops::Deref;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
+use crate::hosting::HostingPreferences;
+use crate::network::NetworkSettings;
 use crate::public_key;
 pub const SEED_SIZE: usize = 32;
 
-fn public_key_from_base64<'de, D>(deserializer: D) -> Result<VerifyingKey, D::Error>
+pub(crate) fn public_key_from_base64<'de, D>(deserializer: D) -> Result<VerifyingKey, D::Error>
 where
     D: Deserializer<'de>,
 {
-    String::deserialize(deserializer)
-        .and_then(|s| {
-            base64::decode_config(s, base64::STANDARD_NO_PAD)
-                .map_err(|err| de::Error::custom(err.to_string()))
-        })
-        .map(|bytes| match bytes[0..32].try_into() {
-            Ok(b) => VerifyingKey::from_bytes(&b).map_err(|e| e.to_string()),
-            Err(_) => Err("Public key is not 32 bytes long".to_string()),
-        })
-        .and_then(|maybe_key| maybe_key.map_err(|err| de::Error::custom(err.to_string())))
+    let s = String::deserialize(deserializer)?;
+    let bytes = base64::decode_config(s, base64::STANDARD_NO_PAD)
+        .map_err(|err| de::Error::custom(err.to_string()))?;
+    let bytes = <[u8; 32]>::try_from(bytes.as_slice())
+        .map_err(|_| de::Error::invalid_length(bytes.len(), &"a 32 byte public key"))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|err| de::Error::custom(err.to_string()))
 }
 
 pub fn seed_from_base64<'de, D>(deserializer: D) -> Result<Seed, D::Error>
 where
     D: Deserializer<'de>,
 {
-    String::deserialize(deserializer)
-        .and_then(|s| base64::decode(s).map_err(|err| de::Error::custom(err.to_string())))
-        .map(|bytes| *array_ref!(bytes, 0, SEED_SIZE))
+    let s = String::deserialize(deserializer)?;
+    let bytes =
+        Zeroizing::new(base64::decode(s).map_err(|err| de::Error::custom(err.to_string()))?);
+    let seed = <[u8; SEED_SIZE]>::try_from(bytes.as_slice())
+        .map_err(|_| de::Error::invalid_length(bytes.len(), &"a 32 byte seed"))?;
+    Ok(Seed::from(seed))
 }
 
-fn to_base64<T, S>(x: &T, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn to_base64<T, S>(x: &T, serializer: S) -> Result<S::Ok, S::Error>
 where
     T: AsRef<[u8]>,
     S: Serializer,
@@ -39,9 +42,57 @@ where
     serializer.serialize_str(&base64::encode_config(x.as_ref(), base64::STANDARD_NO_PAD))
 }
 
+pub(crate) fn signature_from_base64<'de, D>(deserializer: D) -> Result<Signature, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    let bytes = base64::decode_config(s, base64::STANDARD_NO_PAD)
+        .map_err(|err| de::Error::custom(err.to_string()))?;
+    Signature::from_slice(&bytes).map_err(|err| de::Error::custom(err.to_string()))
+}
+
+pub(crate) fn signature_to_base64<S>(
+    signature: &Signature,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    to_base64(&signature.to_bytes(), serializer)
+}
+
 const ARGON2_ADDITIONAL_DATA: &[u8] = b"hpos-config admin ed25519 key v1";
 
-pub type Seed = [u8; SEED_SIZE];
+/// Secret seed of a V1 config, zeroized on drop and redacted from `Debug` output
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Seed([u8; SEED_SIZE]);
+
+impl From<[u8; SEED_SIZE]> for Seed {
+    fn from(bytes: [u8; SEED_SIZE]) -> Self {
+        Seed(bytes)
+    }
+}
+
+impl Deref for Seed {
+    type Target = [u8; SEED_SIZE];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Seed {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Seed(<redacted>)")
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Admin {
@@ -56,6 +107,56 @@ pub struct Admin {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Settings {
     pub admin: Admin,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hosting_preferences: Option<HostingPreferences>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkSettings>,
+}
+
+/// What an admin of a V4 config may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminRole {
+    /// Full access, and the only role allowed to add or remove admins
+    Owner,
+    Operator,
+    ReadOnly,
+}
+
+/// One of several admins of a V4 config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AdminIdentity {
+    pub email: String,
+    #[serde(
+        deserialize_with = "public_key_from_base64",
+        serialize_with = "to_base64"
+    )]
+    pub public_key: VerifyingKey,
+    pub role: AdminRole,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Settings of a V4 config, always holding at least one owner
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdminSettings {
+    #[serde(deserialize_with = "admins_with_owner")]
+    pub admins: Vec<AdminIdentity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hosting_preferences: Option<HostingPreferences>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkSettings>,
+}
+
+fn admins_with_owner<'de, D>(deserializer: D) -> Result<Vec<AdminIdentity>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let admins = Vec::<AdminIdentity>::deserialize(deserializer)?;
+    if !admins.iter().any(|admin| admin.role == AdminRole::Owner) {
+        return Err(de::Error::custom("admins must include an owner"));
+    }
+    Ok(admins)
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -99,6 +200,29 @@ pub enum Config {
         /// The pub-key in settings is the holoport key that is used for verifying login signatures
         settings: Settings,
     },
+    /// Same as V3, with any number of admins, see `Config::apply_admin_change`
+    #[serde(rename = "v4")]
+    V4 {
+        /// This is the Device Seed Bundle as a base64 string which is compatible with lair-keystore >=v0.0.8
+        /// And is encoded with a password that will be needed to be used to decrypt it
+        device_bundle: String,
+        /// Derivation path of the seed in this config that was generated for a Master Seed
+        device_derivation_path: String,
+        // The revocation key is usually the /0 derivation path of the master seed
+        #[serde(
+            deserialize_with = "public_key_from_base64",
+            serialize_with = "to_base64"
+        )]
+        revocation_pub_key: VerifyingKey,
+        // /1 derivation path of the device bundle base36 encoded
+        holoport_id: String,
+        // This is a HoloHash version of the holoport_id
+        initial_host_pub_key: String,
+        /// Holo registration code is used to identify and authenticate its users
+        registration_code: String,
+        /// The admins, at least one of them an owner, and the settings shared with V3
+        settings: AdminSettings,
+    },
 }
 
 impl Config {
@@ -111,6 +235,7 @@ impl Config {
         device_bundle: String,
         device_pub_key: VerifyingKey,
     ) -> Result<(Self, VerifyingKey), Error> {
+        let password = Zeroizing::new(password);
         let admin_keypair = admin_keypair_from(device_pub_key, &email, &password)?;
         let admin = Admin {
             email,
@@ -125,25 +250,62 @@ impl Config {
                 holoport_id,
                 initial_host_pub_key: public_key::to_holochain_encoded_agent_key(&device_pub_key),
                 registration_code,
-                settings: Settings { admin },
+                settings: Settings {
+                    admin,
+                    hosting_preferences: None,
+                    network: None,
+                },
             },
             device_pub_key,
         ))
     }
 
-    pub fn email(&self) -> String {
+    /// Email of the owner, the first one for V4. `None` only for a V4 config
+    /// built by hand without an owner, deserializing one fails
+    pub fn email(&self) -> Option<String> {
+        self.owner().map(|owner| owner.email)
+    }
+
+    /// Public key of the owner, the first one for V4, see `email`
+    pub fn admin_public_key(&self) -> Option<VerifyingKey> {
+        self.owner().map(|owner| owner.public_key)
+    }
+
+    /// All admins, V1-V3 configs have their single admin as owner
+    pub fn admins(&self) -> Vec<AdminIdentity> {
         match self {
             Config::V1 { settings, .. }
             | Config::V2 { settings, .. }
-            | Config::V3 { settings, .. } => settings.admin.email.clone(),
+            | Config::V3 { settings, .. } => vec![AdminIdentity {
+                email: settings.admin.email.clone(),
+                public_key: settings.admin.public_key,
+                role: AdminRole::Owner,
+                label: None,
+            }],
+            Config::V4 { settings, .. } => settings.admins.clone(),
         }
     }
 
-    pub fn admin_public_key(&self) -> VerifyingKey {
+    pub fn admin_by_email(&self, email: &str) -> Option<AdminIdentity> {
+        self.admins().into_iter().find(|admin| admin.email == email)
+    }
+
+    pub fn admin_by_public_key(&self, public_key: &VerifyingKey) -> Option<AdminIdentity> {
+        self.admins()
+            .into_iter()
+            .find(|admin| &admin.public_key == public_key)
+    }
+
+    fn owner(&self) -> Option<AdminIdentity> {
         match self {
-            Config::V1 { settings, .. }
-            | Config::V2 { settings, .. }
-            | Config::V3 { settings, .. } => settings.admin.public_key,
+            Config::V1 { .. } | Config::V2 { .. } | Config::V3 { .. } => {
+                self.admins().into_iter().next()
+            }
+            Config::V4 { settings, .. } => settings
+                .admins
+                .iter()
+                .find(|admin| admin.role == AdminRole::Owner)
+                .cloned(),
         }
     }
 }
@@ -170,10 +332,10 @@ pub fn admin_keypair_from(
 ) -> Result<SigningKey, Error> {
     // This allows to use email addresses shorter than 8 bytes.
     let salt = Sha512::digest(email.as_bytes());
-    let mut hash = [0; SEED_SIZE];
+    let mut hash = Zeroizing::new([0; SEED_SIZE]);
 
     argon2min::Argon2::new(2, 4, 1 << 16, argon2min::Variant::Argon2id)?.hash(
-        &mut hash,
+        &mut *hash,
         password.as_bytes(),
         &salt,
         &holochain_public_key.to_bytes(),
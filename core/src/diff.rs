@@ -0,0 +1,232 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::{
+    fingerprint::{section_fingerprint, short_fingerprint},
+    summary::mask_email,
+    AdminIdentity, AdminRole, Config, ConfigSummary,
+};
+
+/// Which part of a config changed, see `Config::diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Version,
+    Email,
+    AdminKey,
+    /// An admin of a V4 config, by public key
+    AdminAdded,
+    AdminRemoved,
+    /// An admin of a V4 config kept its public key but changed role, label or email
+    AdminChanged,
+    HoloportId,
+    DerivationPath,
+    RevocationKey,
+    RegistrationCode,
+    /// The device bundle now holds a different key, or the key can not be
+    /// compared because the configs don't record the holoport id (v2)
+    DeviceBundle,
+    /// Both configs have a device bundle and record the same holoport id, but
+    /// the bundles differ, e.g. after `relock`. The bundles are not unlocked,
+    /// so whether they still hold the same key is not known.
+    DeviceBundleSameHoloportId,
+    /// A device bundle was added to a config without one, e.g. v1 to v3
+    DeviceBundleAdded,
+    Seed,
+    /// Hosting preferences were added, removed or changed, see `Config::hosting_preferences`
+    HostingPreferences,
+    /// Network settings were added, removed or changed, see `Config::network`
+    Network,
+}
+
+impl ChangeKind {
+    fn description(&self) -> &'static str {
+        match self {
+            ChangeKind::Version => "version changed",
+            ChangeKind::Email => "email changed",
+            ChangeKind::AdminKey => "admin key changed",
+            ChangeKind::AdminAdded => "admin added",
+            ChangeKind::AdminRemoved => "admin removed",
+            ChangeKind::AdminChanged => "admin changed",
+            ChangeKind::HoloportId => "holoport id changed",
+            ChangeKind::DerivationPath => "derivation path changed",
+            ChangeKind::RevocationKey => "revocation key changed",
+            ChangeKind::RegistrationCode => "registration code changed",
+            ChangeKind::DeviceBundle => "device bundle changed",
+            ChangeKind::DeviceBundleSameHoloportId => "device bundle changed, same holoport id",
+            ChangeKind::DeviceBundleAdded => "device bundle added",
+            ChangeKind::Seed => "seed changed",
+            ChangeKind::HostingPreferences => "hosting preferences changed",
+            ChangeKind::Network => "network settings changed",
+        }
+    }
+}
+
+/// A single semantic change between two configs. Values are redacted the
+/// same way as in `ConfigSummary`: emails are masked and secrets or opaque
+/// fields are only given by fingerprint. `None` means the field is absent
+/// in that config version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigChange {
+    pub kind: ChangeKind,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.kind.description(),
+            self.from.as_deref().unwrap_or("-"),
+            self.to.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+impl Config {
+    /// Semantic, redacted changes from `self` to `other`, empty if both
+    /// configs are equivalent
+    pub fn diff(&self, other: &Config) -> Vec<ConfigChange> {
+        let (from, to) = (self.summary(false), other.summary(false));
+        let mut changes = Vec::new();
+        let mut push = |kind, from: Option<&String>, to: Option<&String>| {
+            if from != to {
+                changes.push(ConfigChange {
+                    kind,
+                    from: from.cloned(),
+                    to: to.cloned(),
+                });
+            }
+        };
+
+        push(ChangeKind::Version, Some(&from.version), Some(&to.version));
+        push(
+            ChangeKind::Email,
+            Some(&redact_email(&from.email, &to.email)),
+            Some(&redact_email(&to.email, &from.email)),
+        );
+        push(
+            ChangeKind::AdminKey,
+            Some(&from.admin_public_key),
+            Some(&to.admin_public_key),
+        );
+        // V1-V3 only have the owner, covered by `Email` and `AdminKey`
+        if matches!(self, Config::V4 { .. }) || matches!(other, Config::V4 { .. }) {
+            let (from_admins, to_admins) = (self.admins(), other.admins());
+            for admin in &from_admins {
+                match find_admin(&to_admins, admin) {
+                    Some(to_admin) => push(
+                        ChangeKind::AdminChanged,
+                        Some(&describe_admin(admin, to_admin)),
+                        Some(&describe_admin(to_admin, admin)),
+                    ),
+                    None => push(
+                        ChangeKind::AdminRemoved,
+                        Some(&describe_admin(admin, admin)),
+                        None,
+                    ),
+                }
+            }
+            for admin in &to_admins {
+                if find_admin(&from_admins, admin).is_none() {
+                    push(
+                        ChangeKind::AdminAdded,
+                        None,
+                        Some(&describe_admin(admin, admin)),
+                    );
+                }
+            }
+        }
+        push(
+            ChangeKind::HoloportId,
+            from.holoport_id.as_ref(),
+            to.holoport_id.as_ref(),
+        );
+        push(
+            ChangeKind::DerivationPath,
+            from.derivation_path.as_ref(),
+            to.derivation_path.as_ref(),
+        );
+        push(
+            ChangeKind::RevocationKey,
+            from.revocation_public_key.as_ref(),
+            to.revocation_public_key.as_ref(),
+        );
+        push(
+            ChangeKind::RegistrationCode,
+            from.registration_code.as_ref(),
+            to.registration_code.as_ref(),
+        );
+        push(
+            device_bundle_change(&from, &to),
+            from.device_bundle.as_ref(),
+            to.device_bundle.as_ref(),
+        );
+        push(ChangeKind::Seed, from.seed.as_ref(), to.seed.as_ref());
+        push(
+            ChangeKind::HostingPreferences,
+            self.hosting_preferences().map(section_fingerprint).as_ref(),
+            other
+                .hosting_preferences()
+                .map(section_fingerprint)
+                .as_ref(),
+        );
+        push(
+            ChangeKind::Network,
+            self.network().map(section_fingerprint).as_ref(),
+            other.network().map(section_fingerprint).as_ref(),
+        );
+
+        changes
+    }
+}
+
+/// only consulted when the device bundles differ
+fn device_bundle_change(from: &ConfigSummary, to: &ConfigSummary) -> ChangeKind {
+    match (&from.device_bundle, &to.device_bundle) {
+        (None, Some(_)) => ChangeKind::DeviceBundleAdded,
+        (Some(_), Some(_)) if from.holoport_id.is_some() && from.holoport_id == to.holoport_id => {
+            ChangeKind::DeviceBundleSameHoloportId
+        }
+        _ => ChangeKind::DeviceBundle,
+    }
+}
+
+fn find_admin<'a>(admins: &'a [AdminIdentity], admin: &AdminIdentity) -> Option<&'a AdminIdentity> {
+    admins
+        .iter()
+        .find(|other| other.public_key == admin.public_key)
+}
+
+/// `<public key> (<role>, <masked email>[, <label>])`, see `redact_email` for `other`
+fn describe_admin(admin: &AdminIdentity, other: &AdminIdentity) -> String {
+    let role = match admin.role {
+        AdminRole::Owner => "owner",
+        AdminRole::Operator => "operator",
+        AdminRole::ReadOnly => "read_only",
+    };
+    let mut description = format!(
+        "{} ({}, {}",
+        base64::encode_config(admin.public_key.to_bytes(), base64::STANDARD_NO_PAD),
+        role,
+        redact_email(&admin.email, &other.email)
+    );
+    if let Some(label) = &admin.label {
+        description.push_str(", ");
+        description.push_str(label);
+    }
+    description.push(')');
+    description
+}
+
+/// masked email, with a fingerprint when masking would hide the difference to `other`
+fn redact_email(email: &str, other: &str) -> String {
+    let masked = mask_email(email);
+    if email != other && masked == mask_email(other) {
+        format!("{} ({})", masked, short_fingerprint(email.as_bytes()))
+    } else {
+        masked
+    }
+}
@@ -0,0 +1,278 @@
+//! Encrypted-at-rest container for a whole config, so a lost USB stick does
+//! not expose the owner's email, registration code or admin key.
+//!
+//! The envelope is JSON of the form `{"sealed_v1": {...}}` and is sealed
+//! either with a passphrase (Argon2id + XChaCha20-Poly1305) or for a device:
+//! an ephemeral X25519 key agreement with the Montgomery form of the
+//! holoport's ed25519 key, so only that device can open it.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use failure::{bail, format_err, Error};
+use rand::RngCore;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::Config;
+
+const SEALED_TAG: &str = "sealed_v1";
+const KEY_PERSONAL: &[u8; 16] = b"hpos-config-seal";
+const PASSPHRASE_AD: &[u8] = b"hpos-config envelope";
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+
+// same cost as `admin_keypair_from`
+const ARGON2_PASSES: u32 = 2;
+const ARGON2_LANES: u32 = 4;
+const ARGON2_MEMORY_KIB: u32 = 1 << 16;
+
+/// Who can open a sealed config
+pub enum SealKey<'a> {
+    Passphrase(&'a str),
+    /// The holoport's public key
+    Device(&'a VerifyingKey),
+}
+
+/// Secret matching a `SealKey`
+pub enum UnsealKey<'a> {
+    Passphrase(&'a str),
+    /// The holoport's signing key
+    Device(&'a SigningKey),
+}
+
+/// `UnsealKey` that owns its secret, for long running readers such as
+/// `ConfigWatcher`
+pub enum OwnedUnsealKey {
+    Passphrase(Zeroizing<String>),
+    Device(SigningKey),
+}
+
+impl OwnedUnsealKey {
+    pub fn as_key(&self) -> UnsealKey<'_> {
+        match self {
+            OwnedUnsealKey::Passphrase(passphrase) => UnsealKey::Passphrase(passphrase),
+            OwnedUnsealKey::Device(device_key) => UnsealKey::Device(device_key),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+enum Envelope {
+    #[serde(rename = "sealed_v1")]
+    V1 {
+        recipient: Recipient,
+        #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+        nonce: Vec<u8>,
+        #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+        ciphertext: Vec<u8>,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Recipient {
+    Passphrase {
+        #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+        salt: Vec<u8>,
+        passes: u32,
+        lanes: u32,
+        memory_kib: u32,
+    },
+    Device {
+        #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+        ephemeral_public_key: Vec<u8>,
+    },
+}
+
+fn to_base64<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&base64::encode_config(bytes, base64::STANDARD_NO_PAD))
+}
+
+fn from_base64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    base64::decode_config(s, base64::STANDARD_NO_PAD).map_err(serde::de::Error::custom)
+}
+
+/// Whether `json` holds a sealed config rather than a plain one
+pub fn is_sealed(json: &str) -> bool {
+    serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(json)
+        .map(|map| map.contains_key(SEALED_TAG))
+        .unwrap_or(false)
+}
+
+impl Config {
+    /// Encrypt the whole config into a JSON envelope
+    pub fn seal(&self, key: &SealKey) -> Result<String, Error> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(self)?);
+        let (recipient, cipher_key) = match key {
+            SealKey::Passphrase(passphrase) => {
+                let mut salt = vec![0; SALT_SIZE];
+                rand::thread_rng().fill_bytes(&mut salt);
+                let recipient = Recipient::Passphrase {
+                    salt,
+                    passes: ARGON2_PASSES,
+                    lanes: ARGON2_LANES,
+                    memory_kib: ARGON2_MEMORY_KIB,
+                };
+                let cipher_key = passphrase_key(&recipient, passphrase)?;
+                (recipient, cipher_key)
+            }
+            SealKey::Device(device_key) => {
+                let mut ephemeral = Zeroizing::new([0; 32]);
+                rand::thread_rng().fill_bytes(&mut *ephemeral);
+                let ephemeral = StaticSecret::from(*ephemeral);
+                let ephemeral_public_key = PublicKey::from(&ephemeral);
+                let device_key = PublicKey::from(device_key.to_montgomery().to_bytes());
+                let shared = ephemeral.diffie_hellman(&device_key);
+                let cipher_key = device_cipher_key(
+                    shared.as_bytes(),
+                    ephemeral_public_key.as_bytes(),
+                    device_key.as_bytes(),
+                );
+                let recipient = Recipient::Device {
+                    ephemeral_public_key: ephemeral_public_key.as_bytes().to_vec(),
+                };
+                (recipient, cipher_key)
+            }
+        };
+
+        let mut nonce = vec![0; NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let aad = associated_data(&recipient)?;
+        let ciphertext = XChaCha20Poly1305::new(cipher_key.as_ref().into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| format_err!("failed to encrypt config"))?;
+
+        Ok(serde_json::to_string_pretty(&Envelope::V1 {
+            recipient,
+            nonce,
+            ciphertext,
+        })?)
+    }
+
+    /// Parse a config that may or may not be sealed
+    pub fn unseal(json: &str, key: &UnsealKey) -> Result<Config, Error> {
+        if !is_sealed(json) {
+            return Ok(serde_json::from_str(json)?);
+        }
+        let Envelope::V1 {
+            recipient,
+            nonce,
+            ciphertext,
+        } = serde_json::from_str(json)?;
+        if nonce.len() != NONCE_SIZE {
+            bail!("invalid envelope nonce");
+        }
+
+        let cipher_key = match (&recipient, key) {
+            (Recipient::Passphrase { .. }, UnsealKey::Passphrase(passphrase)) => {
+                passphrase_key(&recipient, passphrase)?
+            }
+            (
+                Recipient::Device {
+                    ephemeral_public_key,
+                },
+                UnsealKey::Device(device_key),
+            ) => {
+                let ephemeral_public_key: [u8; 32] = ephemeral_public_key[..]
+                    .try_into()
+                    .map_err(|_| format_err!("invalid envelope ephemeral key"))?;
+                let ephemeral_public_key = PublicKey::from(ephemeral_public_key);
+                let secret = StaticSecret::from(*Zeroizing::new(device_key.to_scalar_bytes()));
+                let shared = secret.diffie_hellman(&ephemeral_public_key);
+                if !shared.was_contributory() {
+                    bail!("invalid envelope ephemeral key");
+                }
+                device_cipher_key(
+                    shared.as_bytes(),
+                    ephemeral_public_key.as_bytes(),
+                    PublicKey::from(&secret).as_bytes(),
+                )
+            }
+            (Recipient::Passphrase { .. }, UnsealKey::Device(_)) => {
+                bail!("config is sealed with a passphrase")
+            }
+            (Recipient::Device { .. }, UnsealKey::Passphrase(_)) => {
+                bail!("config is sealed for a device key")
+            }
+        };
+
+        let aad = associated_data(&recipient)?;
+        let plaintext = Zeroizing::new(
+            XChaCha20Poly1305::new(cipher_key.as_ref().into())
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: &aad,
+                    },
+                )
+                .map_err(|_| format_err!("wrong key or corrupted envelope"))?,
+        );
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+/// binds the key derivation parameters to the ciphertext
+fn associated_data(recipient: &Recipient) -> Result<Vec<u8>, Error> {
+    let mut aad = SEALED_TAG.as_bytes().to_vec();
+    aad.extend(serde_json::to_vec(recipient)?);
+    Ok(aad)
+}
+
+fn passphrase_key(recipient: &Recipient, passphrase: &str) -> Result<Zeroizing<[u8; 32]>, Error> {
+    let Recipient::Passphrase {
+        salt,
+        passes,
+        lanes,
+        memory_kib,
+    } = recipient
+    else {
+        bail!("not a passphrase envelope");
+    };
+    // the parameters come from the envelope, only accept the ones `seal` writes
+    // instead of letting a crafted file pick the memory and time spent
+    if (*passes, *lanes, *memory_kib) != (ARGON2_PASSES, ARGON2_LANES, ARGON2_MEMORY_KIB) {
+        bail!(
+            "unsupported envelope key derivation parameters: {} passes, {} lanes, {} KiB",
+            passes,
+            lanes,
+            memory_kib
+        );
+    }
+    let mut key = Zeroizing::new([0; 32]);
+    argon2min::Argon2::new(*passes, *lanes, *memory_kib, argon2min::Variant::Argon2id)?.hash(
+        &mut *key,
+        passphrase.as_bytes(),
+        salt,
+        &[],
+        PASSPHRASE_AD,
+    );
+    Ok(key)
+}
+
+fn device_cipher_key(
+    shared: &[u8; 32],
+    ephemeral_public_key: &[u8; 32],
+    device_public_key: &[u8; 32],
+) -> Zeroizing<[u8; 32]> {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(KEY_PERSONAL)
+        .to_state()
+        .update(shared)
+        .update(ephemeral_public_key)
+        .update(device_public_key)
+        .finalize();
+    let mut key = Zeroizing::new([0; 32]);
+    key.copy_from_slice(hash.as_bytes());
+    key
+}
@@ -0,0 +1,58 @@
+use failure::Error;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::Config;
+
+impl Config {
+    /// Canonical serialization: compact JSON with object keys sorted and
+    /// keys, seeds and bundles in the encodings `Config` itself writes.
+    /// Two files describing the same config yield the same bytes regardless
+    /// of whitespace or key order.
+    pub fn canonical_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&sort_keys(serde_json::to_value(
+            self,
+        )?))?)
+    }
+
+    /// Stable identifier of this config: hex blake2b-256 of `canonical_json`
+    pub fn fingerprint(&self) -> Result<String, Error> {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(32)
+            .hash(self.canonical_json()?.as_bytes());
+        Ok(hash.to_hex().to_string())
+    }
+}
+
+/// rebuild objects in sorted key order, independent of serde_json's `preserve_order` feature
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+/// `short_fingerprint` of the canonical JSON of a config section
+pub(crate) fn section_fingerprint<T: Serialize>(section: &T) -> String {
+    let json = serde_json::to_value(section)
+        .map(sort_keys)
+        .and_then(|value| serde_json::to_vec(&value))
+        .unwrap_or_default();
+    short_fingerprint(&json)
+}
+
+/// short blake2b fingerprint of a secret or opaque field
+pub(crate) fn short_fingerprint(data: &[u8]) -> String {
+    let hash = blake2b_simd::Params::new().hash_length(8).hash(data);
+    format!("blake2b:{}", hash.to_hex())
+}
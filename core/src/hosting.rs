@@ -0,0 +1,141 @@
+//! Hosting preferences a HoloPort starts out with, so they can be chosen in
+//! Quickstart instead of being set through separate services after boot.
+
+use failure::{bail, Error};
+use serde::*;
+
+use crate::Config;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Versioned, so the section can evolve without breaking older readers.
+///
+/// Configs are read leniently: versions this reader doesn't know, and v1
+/// preferences that don't parse, end up in `Unknown` instead of failing the
+/// whole config, and values are only checked by `validate`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum HostingPreferences {
+    #[serde(rename = "v1")]
+    V1(HostingPreferencesV1),
+    /// Kept as read, so saving the config doesn't drop it
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+/// Missing fields take their `Default` value. Prices are decimal strings such
+/// as `"0.025"`, so they are stored exactly as given.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HostingPreferencesV1 {
+    /// Longest time in seconds hosted usage is accumulated before an invoice is sent
+    pub max_time_before_invoice_secs: u64,
+    /// HoloFuel per unit of CPU time
+    pub price_compute: String,
+    /// HoloFuel per unit of storage
+    pub price_storage: String,
+    /// HoloFuel per unit of bandwidth
+    pub price_bandwidth: String,
+    /// hApp categories to host, all if `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_categories: Option<Vec<String>>,
+    /// ISO 3166-1 alpha-2 codes of the jurisdictions to host for, all if `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_jurisdictions: Option<Vec<String>>,
+}
+
+impl Default for HostingPreferencesV1 {
+    fn default() -> Self {
+        HostingPreferencesV1 {
+            max_time_before_invoice_secs: 7 * DAY,
+            price_compute: "0.025".to_string(),
+            price_storage: "0.025".to_string(),
+            price_bandwidth: "0.025".to_string(),
+            allowed_categories: None,
+            allowed_jurisdictions: None,
+        }
+    }
+}
+
+impl Default for HostingPreferences {
+    fn default() -> Self {
+        HostingPreferences::V1(HostingPreferencesV1::default())
+    }
+}
+
+impl HostingPreferences {
+    /// Check the values are in range, `Unknown` preferences never are
+    pub fn validate(&self) -> Result<(), Error> {
+        let preferences = match self {
+            HostingPreferences::V1(preferences) => preferences,
+            HostingPreferences::Unknown(_) => {
+                bail!("unsupported hosting preferences, expected v1")
+            }
+        };
+        if !(1..=365 * DAY).contains(&preferences.max_time_before_invoice_secs) {
+            bail!("max time before invoice must be between 1 second and 1 year");
+        }
+        for (name, price) in [
+            ("compute", &preferences.price_compute),
+            ("storage", &preferences.price_storage),
+            ("bandwidth", &preferences.price_bandwidth),
+        ] {
+            if !is_decimal(price) {
+                bail!(
+                    "{} price must be a non-negative decimal such as 0.025",
+                    name
+                );
+            }
+        }
+        for category in preferences.allowed_categories.iter().flatten() {
+            if category.trim().is_empty() {
+                bail!("hApp categories can not be empty");
+            }
+        }
+        for jurisdiction in preferences.allowed_jurisdictions.iter().flatten() {
+            if jurisdiction.len() != 2 || !jurisdiction.chars().all(|c| c.is_ascii_uppercase()) {
+                bail!(
+                    "jurisdiction {} is not an ISO 3166-1 alpha-2 code",
+                    jurisdiction
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Digits with an optional fractional part, e.g. `1`, `0.025`
+fn is_decimal(value: &str) -> bool {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, "0"));
+    [whole, fraction]
+        .iter()
+        .all(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+}
+
+impl Config {
+    /// As read from the file, see `HostingPreferences::validate`
+    pub fn hosting_preferences(&self) -> Option<&HostingPreferences> {
+        match self {
+            Config::V1 { settings, .. }
+            | Config::V2 { settings, .. }
+            | Config::V3 { settings, .. } => settings.hosting_preferences.as_ref(),
+            Config::V4 { settings, .. } => settings.hosting_preferences.as_ref(),
+        }
+    }
+
+    /// Validate and store hosting preferences, `None` removes them
+    pub fn set_hosting_preferences(
+        &mut self,
+        preferences: Option<HostingPreferences>,
+    ) -> Result<(), Error> {
+        if let Some(preferences) = &preferences {
+            preferences.validate()?;
+        }
+        match self {
+            Config::V1 { settings, .. }
+            | Config::V2 { settings, .. }
+            | Config::V3 { settings, .. } => settings.hosting_preferences = preferences,
+            Config::V4 { settings, .. } => settings.hosting_preferences = preferences,
+        }
+        Ok(())
+    }
+}
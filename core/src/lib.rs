@@ -1,4 +1,27 @@
+pub mod admins;
+pub mod conductor;
 pub mod config;
+pub mod diff;
+pub mod envelope;
+pub mod fingerprint;
+pub mod hosting;
+pub mod load;
+pub mod network;
 pub mod public_key;
+#[cfg(feature = "registration")]
+pub mod registration;
+pub mod registration_payload;
+pub mod summary;
+#[cfg(feature = "watch")]
+pub mod watch;
 
-pub use config::{admin_keypair_from, Config};
+pub use admins::{AdminChange, SignedAdminChange};
+pub use conductor::{ConductorOptions, ConductorSetup};
+pub use config::{admin_keypair_from, AdminIdentity, AdminRole, Config};
+pub use diff::{ChangeKind, ConfigChange};
+pub use envelope::{OwnedUnsealKey, SealKey, UnsealKey};
+pub use hosting::{HostingPreferences, HostingPreferencesV1};
+pub use load::{load, load_sealed, save, save_sealed, LoadError};
+pub use network::{Cidr, NetworkSettings, StaticIp};
+pub use registration_payload::{RegistrationPayload, SignedRegistrationPayload};
+pub use summary::ConfigSummary;
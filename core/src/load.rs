@@ -0,0 +1,225 @@
+//! Locating, reading and writing hpos-config files, so binaries and HPOS
+//! services don't each hardcode paths and error handling.
+
+use std::env;
+use std::error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use zeroize::Zeroizing;
+
+use crate::envelope::{is_sealed, SealKey, UnsealKey};
+use crate::Config;
+
+pub const CONFIG_FILE_NAME: &str = "hpos-config.json";
+
+/// Environment variable overriding the well-known locations
+pub const CONFIG_PATH_ENV: &str = "HPOS_CONFIG_PATH";
+
+/// Mount points searched, up to two levels deep, for a USB stick carrying the config
+const MOUNT_ROOTS: &[&str] = &["/media", "/run/media", "/mnt"];
+
+const ETC_PATHS: &[&str] = &["/etc/hpos/hpos-config.json", "/etc/hpos-config.json"];
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// None of the searched paths exist
+    NotFound { searched: Vec<PathBuf> },
+    /// The file exists but can not be read
+    Io { path: PathBuf, source: io::Error },
+    /// The file is not a valid config
+    Malformed {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The file is a sealed config and no key was given, see `load_sealed`
+    Sealed { path: PathBuf },
+    /// The sealed config could not be opened with the given key
+    Unseal { path: PathBuf, reason: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotFound { searched } => {
+                write!(f, "no hpos-config file found, searched:")?;
+                for path in searched {
+                    write!(f, " {}", path.to_string_lossy())?;
+                }
+                Ok(())
+            }
+            LoadError::Io { path, source } => {
+                write!(f, "failed to read {}: {}", path.to_string_lossy(), source)
+            }
+            LoadError::Malformed { path, source } => {
+                write!(f, "malformed config {}: {}", path.to_string_lossy(), source)
+            }
+            LoadError::Sealed { path } => {
+                write!(
+                    f,
+                    "config {} is sealed, a key is required",
+                    path.to_string_lossy()
+                )
+            }
+            LoadError::Unseal { path, reason } => {
+                write!(f, "failed to unseal {}: {}", path.to_string_lossy(), reason)
+            }
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::NotFound { .. } | LoadError::Sealed { .. } | LoadError::Unseal { .. } => {
+                None
+            }
+            LoadError::Io { source, .. } => Some(source),
+            LoadError::Malformed { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Load a config and return it with the path it was read from.
+///
+/// The path is taken from `path` if given, then from `HPOS_CONFIG_PATH`,
+/// and only then searched for in `well_known_paths`. An explicit path or
+/// environment variable that does not exist is an error, not a reason to
+/// keep searching.
+pub fn load(path: Option<&Path>) -> Result<(Config, PathBuf), LoadError> {
+    load_with(path, None)
+}
+
+/// Like `load`, but also opens configs sealed with `save_sealed`
+pub fn load_sealed(path: Option<&Path>, key: &UnsealKey) -> Result<(Config, PathBuf), LoadError> {
+    load_with(path, Some(key))
+}
+
+fn load_with(path: Option<&Path>, key: Option<&UnsealKey>) -> Result<(Config, PathBuf), LoadError> {
+    let explicit = path
+        .map(Path::to_path_buf)
+        .or_else(|| env::var_os(CONFIG_PATH_ENV).map(PathBuf::from));
+    let candidates = match explicit {
+        Some(path) => vec![path],
+        None => well_known_paths(),
+    };
+
+    for path in &candidates {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => Zeroizing::new(json),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(source) => {
+                return Err(LoadError::Io {
+                    path: path.clone(),
+                    source,
+                })
+            }
+        };
+        let path = path.clone();
+        return match (is_sealed(&json), key) {
+            (false, _) => match serde_json::from_str(&json) {
+                Ok(config) => Ok((config, path)),
+                Err(source) => Err(LoadError::Malformed { path, source }),
+            },
+            (true, None) => Err(LoadError::Sealed { path }),
+            (true, Some(key)) => match Config::unseal(&json, key) {
+                Ok(config) => Ok((config, path)),
+                Err(err) => Err(LoadError::Unseal {
+                    path,
+                    reason: err.to_string(),
+                }),
+            },
+        };
+    }
+    Err(LoadError::NotFound {
+        searched: candidates,
+    })
+}
+
+/// Locations searched by `load` in order: mounted USB sticks, then /etc
+pub fn well_known_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for root in MOUNT_ROOTS {
+        let root = Path::new(root);
+        paths.push(root.join(CONFIG_FILE_NAME));
+        for dir in sorted_subdirs(root) {
+            paths.push(dir.join(CONFIG_FILE_NAME));
+            for dir in sorted_subdirs(&dir) {
+                paths.push(dir.join(CONFIG_FILE_NAME));
+            }
+        }
+    }
+    paths.extend(ETC_PATHS.iter().map(PathBuf::from));
+    paths
+}
+
+fn sorted_subdirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Write the config to `path` atomically: the JSON goes to a temporary file
+/// next to it, readable by the owner only, which is then renamed over `path`.
+/// A crash never leaves a half written config behind.
+pub fn save(config: &Config, path: &Path) -> io::Result<()> {
+    write_atomically(path, serde_json::to_string_pretty(config)?.as_bytes())
+}
+
+/// Like `save`, but writes the config sealed for `key`
+pub fn save_sealed(config: &Config, path: &Path, key: &SealKey) -> io::Result<()> {
+    let sealed = config
+        .seal(key)
+        .map_err(|err| io::Error::other(err.to_string()))?;
+    write_atomically(path, sealed.as_bytes())
+}
+
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let (tmp_path, mut tmp_file) = create_temporary(path)?;
+    let written = tmp_file
+        .write_all(contents)
+        .and_then(|()| tmp_file.sync_all())
+        .and_then(|()| fs::rename(&tmp_path, path));
+    if let Err(err) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        // persist the rename itself, not supported on every platform
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+/// A new file next to `path`, readable by the owner only. The name is random
+/// and the file must not exist yet, so existing files or symlinks are never
+/// followed and concurrent writers don't share a temporary file.
+fn create_temporary(path: &Path) -> io::Result<(PathBuf, File)> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    loop {
+        let mut tmp_name = std::ffi::OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(&tmp_path) {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
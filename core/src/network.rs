@@ -0,0 +1,169 @@
+//! Network settings a HoloPort needs before it can reach Holo services,
+//! e.g. on networks without DHCP or with a mandatory proxy.
+
+use failure::{bail, format_err, Error};
+use serde::*;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use url::Url;
+
+use crate::Config;
+
+/// All fields are optional and absent ones are left to HPOS defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NetworkSettings {
+    /// Static address instead of DHCP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub static_ip: Option<StaticIp>,
+    /// DNS servers overriding the ones handed out by DHCP
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dns_servers: Vec<IpAddr>,
+    /// http, https or socks5 proxy for outgoing connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Url>,
+    /// Holochain bootstrap server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bootstrap_url: Option<Url>,
+    /// Holochain signal server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal_url: Option<Url>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StaticIp {
+    pub address: Cidr,
+    pub gateway: IpAddr,
+}
+
+/// An address with its network prefix length, `192.168.1.10/24`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    pub address: IpAddr,
+    pub prefix_len: u8,
+}
+
+impl FromStr for Cidr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = s
+            .split_once('/')
+            .ok_or_else(|| format_err!("{} is missing a /prefix length", s))?;
+        let address: IpAddr = address.parse()?;
+        let prefix_len: u8 = prefix_len.parse()?;
+        let max_len = if address.is_ipv4() { 32 } else { 128 };
+        if prefix_len > max_len {
+            bail!("prefix length of {} is larger than {}", s, max_len);
+        }
+        Ok(Cidr {
+            address,
+            prefix_len,
+        })
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix_len)
+    }
+}
+
+impl Serialize for Cidr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cidr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Cidr {
+    fn contains(&self, address: &IpAddr) -> bool {
+        let mask = |bits: u32, len: u8| -> u128 {
+            let host_bits = bits - u32::from(len);
+            if host_bits >= 128 {
+                0
+            } else {
+                u128::MAX << host_bits
+            }
+        };
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = mask(32, self.prefix_len);
+                u128::from(u32::from(network)) & mask == u128::from(u32::from(*address)) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = mask(128, self.prefix_len);
+                u128::from(network) & mask == u128::from(*address) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl NetworkSettings {
+    /// Checked by `Config::set_network`. Reading a config only checks that
+    /// every address, prefix and url parses, so a malformed one makes the whole
+    /// config unreadable, while a gateway outside its network or a url with
+    /// the wrong scheme is read as is and only fails here
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(StaticIp { address, gateway }) = &self.static_ip {
+            if !address.contains(gateway) {
+                bail!("gateway {} is not in the network {}", gateway, address);
+            }
+        }
+        check_scheme("proxy", &self.proxy, &["http", "https", "socks5"])?;
+        check_scheme("bootstrap url", &self.bootstrap_url, &["http", "https"])?;
+        check_scheme("signal url", &self.signal_url, &["ws", "wss"])?;
+        Ok(())
+    }
+}
+
+fn check_scheme(name: &str, url: &Option<Url>, schemes: &[&str]) -> Result<(), Error> {
+    if let Some(url) = url {
+        if !schemes.contains(&url.scheme()) {
+            bail!(
+                "{} {} must use one of the schemes {}",
+                name,
+                url,
+                schemes.join(", ")
+            );
+        }
+        if !url.has_host() {
+            bail!("{} {} has no host", name, url);
+        }
+    }
+    Ok(())
+}
+
+impl Config {
+    /// As read from the file, see `NetworkSettings::validate`
+    pub fn network(&self) -> Option<&NetworkSettings> {
+        match self {
+            Config::V1 { settings, .. }
+            | Config::V2 { settings, .. }
+            | Config::V3 { settings, .. } => settings.network.as_ref(),
+            Config::V4 { settings, .. } => settings.network.as_ref(),
+        }
+    }
+
+    /// Validate and store network settings, `None` removes them
+    pub fn set_network(&mut self, network: Option<NetworkSettings>) -> Result<(), Error> {
+        if let Some(network) = &network {
+            network.validate()?;
+        }
+        match self {
+            Config::V1 { settings, .. }
+            | Config::V2 { settings, .. }
+            | Config::V3 { settings, .. } => settings.network = network,
+            Config::V4 { settings, .. } => settings.network = network,
+        }
+        Ok(())
+    }
+}
@@ -6,6 +6,15 @@ pub fn to_base36_id(public_key: &VerifyingKey) -> String {
     base36::encode(&public_key.to_bytes())
 }
 
+pub fn from_base36_id(id: &str) -> Fallible<VerifyingKey> {
+    let bytes = base36::decode(id).map_err(|err| format_err!("{}", err))?;
+    let bytes: [u8; 32] = match bytes.get(0..32).map(<[u8; 32]>::try_from) {
+        Some(Ok(b)) => b,
+        _ => bail!("Holoport id is not 32 bytes in length"),
+    };
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
 pub fn to_url(public_key: &VerifyingKey) -> Fallible<Url> {
     let url = format!("https://{}.holohost.net", to_base36_id(public_key));
     Ok(Url::parse(&url)?)
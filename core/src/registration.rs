@@ -0,0 +1,133 @@
+//! Client for the membrane proof service's registration code check, so a
+//! wrong code is caught while generating a config instead of when the
+//! HoloPort first joins the network. Works natively and in wasm, where
+//! reqwest uses `fetch`.
+
+use serde::*;
+use std::error;
+use std::fmt;
+use url::Url;
+
+/// Relative to the membrane proof service url, e.g. https://hbs.holo.host
+pub const VERIFY_PATH: &str = "registration/api/v1/verify-registration-code";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Host,
+}
+
+/// Body of a verify request
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct VerifyRequest {
+    pub role: Role,
+    pub email: String,
+    pub code: String,
+}
+
+/// Body of an error response, always sent with status 500
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceError {
+    pub error: String,
+    pub info: String,
+    /// Whether `info` is meant for the user, e.g. "unknown registration code"
+    pub is_displayed_to_user: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    Valid,
+    /// The service refused the code, with a reason to show to the user
+    Rejected(String),
+}
+
+#[derive(Debug)]
+pub enum RegistrationError {
+    /// The service could not be reached or its response not read
+    Http(reqwest::Error),
+    /// The service responded with a status other than 200, 201 or 500
+    Status(u16),
+    /// The service failed with an error that is not meant for the user
+    Service(ServiceError),
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistrationError::Http(err) => {
+                write!(f, "failed to reach membrane proof service: {}", err)
+            }
+            RegistrationError::Status(status) => {
+                write!(f, "service responded with status code {}", status)
+            }
+            RegistrationError::Service(err) => {
+                write!(f, "service error: {}: {}", err.error, err.info)
+            }
+        }
+    }
+}
+
+impl error::Error for RegistrationError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RegistrationError::Http(err) => Some(err),
+            RegistrationError::Status(_) | RegistrationError::Service(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for RegistrationError {
+    fn from(err: reqwest::Error) -> Self {
+        RegistrationError::Http(err)
+    }
+}
+
+pub struct RegistrationClient {
+    verify_url: Url,
+    http: reqwest::Client,
+}
+
+impl RegistrationClient {
+    /// Client for the service at `service_url`, the same value Quickstart
+    /// takes as `MEMBRANE_PROOF_SERVICE_URL`
+    pub fn new(service_url: &Url) -> Result<Self, url::ParseError> {
+        // keep a path prefix like https://example.com/hbs as a directory
+        let mut base = service_url.clone();
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(RegistrationClient {
+            verify_url: base.join(VERIFY_PATH)?,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Ask the service whether `code` is a valid host registration code
+    /// for `email`
+    pub async fn verify(&self, email: &str, code: &str) -> Result<Verification, RegistrationError> {
+        let request = VerifyRequest {
+            role: Role::Host,
+            email: email.to_string(),
+            code: code.to_string(),
+        };
+        let response = self
+            .http
+            .post(self.verify_url.clone())
+            .json(&request)
+            .send()
+            .await?;
+        match response.status().as_u16() {
+            200 | 201 => Ok(Verification::Valid),
+            500 => {
+                let err: ServiceError = response.json().await?;
+                if err.is_displayed_to_user {
+                    Ok(Verification::Rejected(err.info))
+                } else {
+                    Err(RegistrationError::Service(err))
+                }
+            }
+            status => Err(RegistrationError::Status(status)),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Registration code signed by a HoloPort's holoport key, which only the
+//! unlocked device bundle holds. The payload carries its own holoport id, so
+//! the signature only proves that the sender holds the key of that id, not
+//! which HoloPort it is. A service like HBS must still check the id against
+//! the one it expects for the registration code.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use failure::{bail, format_err, Error};
+use serde::*;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::{signature_from_base64, signature_to_base64};
+use crate::{public_key, Config};
+
+const SIGNATURE_CONTEXT: &[u8] = b"hpos-config registration payload v1";
+
+/// Tolerated clock difference between a HoloPort and the verifying service
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegistrationPayload {
+    pub registration_code: String,
+    /// base36 holoport key, see `public_key::to_base36_id`
+    pub holoport_id: String,
+    /// Holochain encoded holoport key
+    pub agent_key: String,
+    /// Seconds since the unix epoch when the payload was signed
+    pub timestamp: u64,
+}
+
+/// A `RegistrationPayload` with the signature of the holoport key
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SignedRegistrationPayload {
+    pub payload: RegistrationPayload,
+    #[serde(
+        deserialize_with = "signature_from_base64",
+        serialize_with = "signature_to_base64"
+    )]
+    pub signature: Signature,
+}
+
+impl Config {
+    /// Sign the registration code of this config with `holoport_key`, the
+    /// key of its device bundle, as of `now`
+    pub fn registration_payload(
+        &self,
+        holoport_key: &SigningKey,
+        now: SystemTime,
+    ) -> Result<SignedRegistrationPayload, Error> {
+        let public_key = holoport_key.verifying_key();
+        let registration_code = match self {
+            Config::V1 { .. } => bail!("V1 configs do not carry a registration code"),
+            Config::V2 {
+                registration_code, ..
+            } => registration_code,
+            Config::V3 {
+                registration_code,
+                holoport_id,
+                ..
+            }
+            | Config::V4 {
+                registration_code,
+                holoport_id,
+                ..
+            } => {
+                if public_key::to_base36_id(&public_key) != *holoport_id {
+                    bail!("holoport key does not belong to holoport {}", holoport_id);
+                }
+                registration_code
+            }
+        };
+
+        let payload = RegistrationPayload {
+            registration_code: registration_code.clone(),
+            holoport_id: public_key::to_base36_id(&public_key),
+            agent_key: public_key::to_holochain_encoded_agent_key(&public_key),
+            timestamp: now.duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+        let signature = holoport_key.sign(&signed_message(&payload)?);
+        Ok(SignedRegistrationPayload { payload, signature })
+    }
+}
+
+impl SignedRegistrationPayload {
+    /// Check the signature and that the payload was signed at most `max_age`
+    /// before `now`, returning the holoport key that signed it. This proves
+    /// possession of that key, not identity: anyone can sign a payload with a
+    /// fresh key. Whether the key belongs to the holoport registered for the
+    /// code, and whether the code itself is valid, is up to the service.
+    pub fn verify(&self, now: SystemTime, max_age: Duration) -> Result<VerifyingKey, Error> {
+        let payload = &self.payload;
+        let holoport_key = public_key::from_base36_id(&payload.holoport_id)?;
+        if public_key::to_holochain_encoded_agent_key(&holoport_key) != payload.agent_key {
+            bail!(
+                "agent key does not belong to holoport {}",
+                payload.holoport_id
+            );
+        }
+        holoport_key
+            .verify_strict(&signed_message(payload)?, &self.signature)
+            .map_err(|_| format_err!("registration payload signature is invalid"))?;
+
+        let signed_at = UNIX_EPOCH
+            .checked_add(Duration::from_secs(payload.timestamp))
+            .ok_or_else(|| format_err!("registration payload timestamp is out of range"))?;
+        if now
+            .checked_add(MAX_CLOCK_SKEW)
+            .is_some_and(|latest| signed_at > latest)
+        {
+            bail!("registration payload is signed in the future");
+        }
+        if now.duration_since(signed_at).unwrap_or_default() > max_age {
+            bail!("registration payload has expired");
+        }
+        Ok(holoport_key)
+    }
+}
+
+fn signed_message(payload: &RegistrationPayload) -> Result<Vec<u8>, Error> {
+    let mut message = SIGNATURE_CONTEXT.to_vec();
+    message.extend(serde_json::to_vec(payload)?);
+    Ok(message)
+}
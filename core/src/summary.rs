@@ -0,0 +1,177 @@
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use serde::Serialize;
+use std::fmt;
+
+use crate::{fingerprint::short_fingerprint as fingerprint, public_key, Config};
+
+/// Redacted view of a config that is safe to share, e.g. in support tickets.
+/// Secrets and device bundles are only shown by fingerprint.
+#[derive(Debug, Serialize)]
+pub struct ConfigSummary {
+    pub version: String,
+    /// `Config::fingerprint` of the whole config
+    pub fingerprint: String,
+    pub email: String,
+    pub admin_public_key: String,
+    /// Number of admins, only shown for configs with more than one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admins: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holoport_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation_public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_bundle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<String>,
+}
+
+impl Config {
+    /// Redacted summary of this config, optionally masking the admin email
+    pub fn summary(&self, mask_email: bool) -> ConfigSummary {
+        let email = self.email().unwrap_or_default();
+        let email = if mask_email {
+            self::mask_email(&email)
+        } else {
+            email
+        };
+        let mut summary = ConfigSummary {
+            version: String::new(),
+            fingerprint: self.fingerprint().unwrap_or_default(),
+            email,
+            admin_public_key: self
+                .admin_public_key()
+                .map(|key| encode_key(&key))
+                .unwrap_or_default(),
+            admins: Some(self.admins().len())
+                .filter(|count| *count > 1)
+                .map(|count| count.to_string()),
+            holoport_id: None,
+            url: None,
+            agent_key: None,
+            derivation_path: None,
+            revocation_public_key: None,
+            registration_code: None,
+            device_bundle: None,
+            seed: None,
+        };
+
+        match self {
+            Config::V1 { seed, .. } => {
+                summary.version = "v1".into();
+                summary.seed = Some(fingerprint(seed.as_ref()));
+                summary.set_holoport_key(&SigningKey::from_bytes(seed).verifying_key());
+            }
+            Config::V2 {
+                device_bundle,
+                derivation_path,
+                registration_code,
+                ..
+            } => {
+                summary.version = "v2".into();
+                summary.derivation_path = Some(derivation_path.clone());
+                summary.registration_code = Some(fingerprint(registration_code.as_bytes()));
+                summary.device_bundle = Some(fingerprint(device_bundle.as_bytes()));
+            }
+            Config::V3 {
+                device_bundle,
+                device_derivation_path,
+                revocation_pub_key,
+                holoport_id,
+                initial_host_pub_key,
+                registration_code,
+                ..
+            }
+            | Config::V4 {
+                device_bundle,
+                device_derivation_path,
+                revocation_pub_key,
+                holoport_id,
+                initial_host_pub_key,
+                registration_code,
+                ..
+            } => {
+                summary.version = match self {
+                    Config::V3 { .. } => "v3".into(),
+                    _ => "v4".into(),
+                };
+                if let Ok(holoport_key) = public_key::from_base36_id(holoport_id) {
+                    summary.set_holoport_key(&holoport_key);
+                }
+                summary.holoport_id = Some(holoport_id.clone());
+                summary.agent_key = Some(initial_host_pub_key.clone());
+                summary.derivation_path = Some(device_derivation_path.clone());
+                summary.revocation_public_key = Some(encode_key(revocation_pub_key));
+                summary.registration_code = Some(fingerprint(registration_code.as_bytes()));
+                summary.device_bundle = Some(fingerprint(device_bundle.as_bytes()));
+            }
+        }
+        summary
+    }
+}
+
+impl ConfigSummary {
+    fn set_holoport_key(&mut self, holoport_key: &VerifyingKey) {
+        self.holoport_id = Some(public_key::to_base36_id(holoport_key));
+        self.url = public_key::to_url(holoport_key)
+            .ok()
+            .map(|url| url.to_string());
+        self.agent_key = Some(public_key::to_holochain_encoded_agent_key(holoport_key));
+    }
+}
+
+impl fmt::Display for ConfigSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [
+            ("version", Some(&self.version)),
+            ("fingerprint", Some(&self.fingerprint)),
+            ("email", Some(&self.email)),
+            ("admin public key", Some(&self.admin_public_key)),
+            ("admins", self.admins.as_ref()),
+            ("holoport id", self.holoport_id.as_ref()),
+            ("url", self.url.as_ref()),
+            ("agent key", self.agent_key.as_ref()),
+            ("derivation path", self.derivation_path.as_ref()),
+            ("revocation key", self.revocation_public_key.as_ref()),
+            ("registration code", self.registration_code.as_ref()),
+            ("device bundle", self.device_bundle.as_ref()),
+            ("seed", self.seed.as_ref()),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                writeln!(f, "{:<19}{}", format!("{}:", name), value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints the redacted summary, never secrets
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary(false).fmt(f)
+    }
+}
+
+fn encode_key(public_key: &VerifyingKey) -> String {
+    base64::encode_config(public_key.to_bytes(), base64::STANDARD_NO_PAD)
+}
+
+/// keep the first character of the local part and the domain, `j***@holo.host`
+pub(crate) fn mask_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            let first: String = local.chars().take(1).collect();
+            format!("{}***@{}", first, domain)
+        }
+        None => "***".into(),
+    }
+}
@@ -0,0 +1,195 @@
+//! Reload events for services that keep a config in memory, so an admin
+//! password change or re-flash doesn't require a restart.
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+use crate::{load, load_sealed, Config, ConfigChange, LoadError, OwnedUnsealKey};
+
+/// Quiet period after the last file system event before the config is re-read
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// The config was re-read and its `Config::fingerprint` differs from the
+    /// previous one. `changes` describes what changed; match on
+    /// `ConfigChange::kind`, e.g. `ChangeKind::AdminKey` or
+    /// `ChangeKind::HostingPreferences`, to react to specific changes. It is
+    /// empty if the change is not one `Config::diff` reports on.
+    Changed {
+        config: Arc<Config>,
+        changes: Vec<ConfigChange>,
+    },
+    /// The file could not be read or parsed, the previous config stays current
+    Failed(LoadError),
+}
+
+#[derive(Debug)]
+pub enum WatchError {
+    /// The initial config could not be loaded
+    Load(LoadError),
+    /// The file system watch could not be set up
+    Notify(notify::Error),
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchError::Load(err) => err.fmt(f),
+            WatchError::Notify(err) => write!(f, "failed to watch config: {}", err),
+        }
+    }
+}
+
+impl error::Error for WatchError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            WatchError::Load(err) => Some(err),
+            WatchError::Notify(err) => Some(err),
+        }
+    }
+}
+
+/// Watches a config file and re-parses it whenever it changes.
+///
+/// The parent directory is watched rather than the file itself, so configs
+/// replaced by `save` or an editor's rename are picked up too. Dropping the
+/// watcher stops it.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+    config: watch::Receiver<Arc<Config>>,
+    events: mpsc::Receiver<WatchEvent>,
+}
+
+impl ConfigWatcher {
+    /// Load the config at `path` and start watching it. Must be called from
+    /// within a tokio runtime.
+    pub fn new(path: &Path, debounce: Duration) -> Result<Self, WatchError> {
+        Self::start(path, debounce, None)
+    }
+
+    /// Like `new`, but also opens configs sealed with `save_sealed`
+    pub fn new_sealed(
+        path: &Path,
+        debounce: Duration,
+        key: OwnedUnsealKey,
+    ) -> Result<Self, WatchError> {
+        Self::start(path, debounce, Some(key))
+    }
+
+    fn start(
+        path: &Path,
+        debounce: Duration,
+        key: Option<OwnedUnsealKey>,
+    ) -> Result<Self, WatchError> {
+        let (config, path) = read(path, key.as_ref()).map_err(WatchError::Load)?;
+        let (config_tx, config_rx) = watch::channel(Arc::new(config));
+        let (events_tx, events_rx) = mpsc::channel(16);
+        let (raw_tx, raw_rx) = mpsc::unbounded_channel();
+
+        let file_name = path.file_name().map(|name| name.to_owned());
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let relevant = match event {
+                    Ok(event) => event
+                        .paths
+                        .iter()
+                        .any(|changed| changed.file_name() == file_name.as_deref()),
+                    // let the reload surface the problem
+                    Err(_) => true,
+                };
+                if relevant {
+                    let _ = raw_tx.send(());
+                }
+            })
+            .map_err(WatchError::Notify)?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(WatchError::Notify)?;
+
+        let task = tokio::spawn(reload_loop(
+            path, key, debounce, raw_rx, config_tx, events_tx,
+        ));
+
+        Ok(ConfigWatcher {
+            _watcher: watcher,
+            task,
+            config: config_rx,
+            events: events_rx,
+        })
+    }
+
+    /// The last config that was read successfully
+    pub fn config(&self) -> Arc<Config> {
+        self.config.borrow().clone()
+    }
+
+    /// Wait for the next event, `None` once the watcher has stopped
+    pub async fn recv(&mut self) -> Option<WatchEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn read(path: &Path, key: Option<&OwnedUnsealKey>) -> Result<(Config, PathBuf), LoadError> {
+    match key {
+        Some(key) => load_sealed(Some(path), &key.as_key()),
+        None => load(Some(path)),
+    }
+}
+
+async fn reload_loop(
+    path: PathBuf,
+    key: Option<OwnedUnsealKey>,
+    debounce: Duration,
+    mut raw_rx: mpsc::UnboundedReceiver<()>,
+    config_tx: watch::Sender<Arc<Config>>,
+    events_tx: mpsc::Sender<WatchEvent>,
+) {
+    while raw_rx.recv().await.is_some() {
+        // wait until the file has been quiet for `debounce`
+        loop {
+            match tokio::time::timeout(debounce, raw_rx.recv()).await {
+                Ok(Some(())) => continue,
+                Ok(None) => return,
+                Err(_) => break,
+            }
+        }
+
+        let event = match read(&path, key.as_ref()) {
+            Ok((config, _)) => {
+                let previous = config_tx.borrow().clone();
+                if let (Ok(previous), Ok(current)) = (previous.fingerprint(), config.fingerprint())
+                {
+                    if previous == current {
+                        continue;
+                    }
+                }
+                let changes = previous.diff(&config);
+                let config = Arc::new(config);
+                config_tx.send_replace(config.clone());
+                WatchEvent::Changed { config, changes }
+            }
+            Err(err) => WatchEvent::Failed(err),
+        };
+        if events_tx.send(event).await.is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,6 @@
+#!/bin/sh
+# Generated by hpos-config for the HoloPort with agent key {{agent_key}}
+# Imports its device bundle into lair as seed {{seed_tag}}. Reads the lair
+# passphrase and then the device bundle passphrase from stdin.
+set -e
+exec lair-keystore --lair-root {{keystore_path}} import-seed --piped {{seed_tag}} {{device_bundle}}
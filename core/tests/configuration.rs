@@ -2,7 +2,14 @@
 mod tests {
 
     use ed25519_dalek::VerifyingKey;
-    use hpos_config_core::Config;
+    use ed25519_dalek::{Signer, SigningKey};
+    use hpos_config_core::{
+        admin_keypair_from, load, load_sealed, save, save_sealed, AdminChange, AdminIdentity,
+        AdminRole, ChangeKind, Cidr, ConductorOptions, Config, HostingPreferences,
+        HostingPreferencesV1, LoadError, NetworkSettings, SealKey, SignedRegistrationPayload,
+        StaticIp, UnsealKey,
+    };
+    use std::time::{Duration, UNIX_EPOCH};
 
     #[tokio::test(flavor = "multi_thread")]
     async fn test_hpos_config() -> Result<(), String> {
@@ -76,4 +83,594 @@ mod tests {
             return Err("Expected V3 variant".to_string());
         }
     }
+
+    const V1_CONFIG: &str = r#"{"v1":{"seed":"BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc=","settings":{"admin":{"email":"joel@holo.host","public_key":"6kpsY+KcUgq+9VB7Ey7F+ZVHdq6+vnuSQh7qaRRG0iw"}}}}"#;
+
+    #[test]
+    fn v1_seed_is_redacted() {
+        let config: Config = serde_json::from_str(V1_CONFIG).unwrap();
+
+        let debug = format!("{:?}", config);
+        assert!(debug.contains("Seed(<redacted>)"));
+        assert!(!debug.contains("7, 7, 7"));
+
+        if let Config::V1 { seed, .. } = &config {
+            assert_eq!(**seed, [7; 32]);
+        }
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains("BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc"));
+
+        // short seeds and keys are a parse error, not a panic
+        for (field, short) in [
+            ("BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc=", "AAAA"),
+            ("6kpsY+KcUgq+9VB7Ey7F+ZVHdq6+vnuSQh7qaRRG0iw", "AAAA"),
+        ] {
+            let err = serde_json::from_str::<Config>(&V1_CONFIG.replace(field, short)).unwrap_err();
+            assert!(err.to_string().contains("invalid length 3"));
+        }
+    }
+
+    #[test]
+    fn summary_is_redacted() {
+        let config: Config = serde_json::from_str(V1_CONFIG).unwrap();
+
+        let summary = config.summary(true);
+        assert_eq!(summary.version, "v1");
+        assert_eq!(summary.email, "j***@holo.host");
+        assert!(summary.seed.as_ref().unwrap().starts_with("blake2b:"));
+        assert_eq!(
+            summary.url.as_deref(),
+            Some(format!("https://{}.holohost.net/", summary.holoport_id.unwrap()).as_str())
+        );
+
+        let display = config.to_string();
+        assert!(display.contains("joel@holo.host"));
+        assert!(!display.contains("BwcHBwcH"));
+    }
+
+    #[test]
+    fn fingerprint_ignores_formatting() {
+        let config: Config = serde_json::from_str(V1_CONFIG).unwrap();
+        let value: serde_json::Value = serde_json::from_str(V1_CONFIG).unwrap();
+        // reordered keys and indentation must not matter
+        let reformatted = format!(
+            "{{\n  \"v1\": {{\n    \"settings\": {},\n    \"seed\": {}\n  }}\n}}",
+            serde_json::to_string_pretty(&value["v1"]["settings"]).unwrap(),
+            value["v1"]["seed"]
+        );
+        let other: Config = serde_json::from_str(&reformatted).unwrap();
+
+        assert_eq!(
+            config.canonical_json().unwrap(),
+            other.canonical_json().unwrap()
+        );
+        assert_eq!(config.fingerprint().unwrap(), other.fingerprint().unwrap());
+        assert_eq!(config.fingerprint().unwrap().len(), 64);
+        assert!(!config
+            .canonical_json()
+            .unwrap()
+            .contains(char::is_whitespace));
+    }
+
+    #[test]
+    fn diff_reports_semantic_changes() {
+        let config_with = |device_bundle: &str, device_key: [u8; 32]| {
+            let device_pub_key = SigningKey::from_bytes(&device_key).verifying_key();
+            Config::new(
+                "joel@holo.host".into(),
+                "password".into(),
+                "registration-code".into(),
+                device_pub_key,
+                "1".into(),
+                device_bundle.into(),
+                device_pub_key,
+            )
+            .unwrap()
+            .0
+        };
+        let original = config_with("bundle", [1; 32]);
+
+        assert!(original.diff(&original).is_empty());
+
+        let relocked = config_with("relocked-bundle", [1; 32]);
+        let kinds: Vec<_> = original
+            .diff(&relocked)
+            .into_iter()
+            .map(|change| change.kind)
+            .collect();
+        assert_eq!(kinds, vec![ChangeKind::DeviceBundleSameHoloportId]);
+
+        let reflashed = config_with("other-bundle", [2; 32]);
+        let changes = original.diff(&reflashed);
+        let kinds: Vec<_> = changes.iter().map(|change| change.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ChangeKind::AdminKey,
+                ChangeKind::HoloportId,
+                ChangeKind::RevocationKey,
+                ChangeKind::DeviceBundle
+            ]
+        );
+        let rendered = changes[3].to_string();
+        assert!(rendered.starts_with("device bundle changed: blake2b:"));
+        assert!(!rendered.contains("other-bundle"));
+
+        // a v1 config only has the seed, upgrading it adds the device bundle
+        let v1: Config = serde_json::from_str(V1_CONFIG).unwrap();
+        let upgraded = config_with("bundle", [7; 32]);
+        let kinds: Vec<_> = v1
+            .diff(&upgraded)
+            .into_iter()
+            .map(|change| change.kind)
+            .collect();
+        assert!(kinds.contains(&ChangeKind::DeviceBundleAdded));
+        assert!(!kinds.contains(&ChangeKind::DeviceBundleSameHoloportId));
+    }
+
+    #[test]
+    fn diff_reports_admin_changes() {
+        let device_pub_key = SigningKey::from_bytes(&[1; 32]).verifying_key();
+        let (config, _) = Config::new(
+            "owner@holo.host".into(),
+            "password".into(),
+            "registration-code".into(),
+            device_pub_key,
+            "1".into(),
+            "bundle".into(),
+            device_pub_key,
+        )
+        .unwrap();
+        let v4 = serde_json::to_value(config.into_v4().unwrap()).unwrap();
+        let with_admins = |admins: &[(u8, &str)]| {
+            let mut value = v4.clone();
+            let list = value["v4"]["settings"]["admins"].as_array_mut().unwrap();
+            for (key, role) in admins {
+                let public_key = SigningKey::from_bytes(&[*key; 32]).verifying_key();
+                list.push(serde_json::json!({
+                    "email": "operator@holo.host",
+                    "public_key": base64::encode_config(public_key.to_bytes(), base64::STANDARD_NO_PAD),
+                    "role": role,
+                }));
+            }
+            serde_json::from_value::<Config>(value).unwrap()
+        };
+        let kinds = |from: &Config, to: &Config| -> Vec<_> {
+            from.diff(to)
+                .into_iter()
+                .map(|change| change.kind)
+                .collect()
+        };
+
+        // same number of admins, different operator
+        let original = with_admins(&[(5, "operator")]);
+        let replaced = with_admins(&[(6, "operator")]);
+        assert_eq!(
+            kinds(&original, &replaced),
+            vec![ChangeKind::AdminRemoved, ChangeKind::AdminAdded]
+        );
+
+        let demoted = with_admins(&[(5, "read_only")]);
+        let changes = original.diff(&demoted);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ChangeKind::AdminChanged);
+        assert!(changes[0]
+            .to_string()
+            .contains("(operator, o***@holo.host) -> "));
+        assert!(changes[0]
+            .to_string()
+            .ends_with("(read_only, o***@holo.host)"));
+
+        assert!(original.diff(&with_admins(&[(5, "operator")])).is_empty());
+    }
+
+    #[test]
+    fn load_and_save_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hpos-config.json");
+
+        match load(Some(&path)) {
+            Err(LoadError::NotFound { searched }) => assert_eq!(searched, vec![path.clone()]),
+            other => panic!("expected NotFound, got {:?}", other),
+        }
+
+        std::fs::write(&path, "{\"v1\":{}}").unwrap();
+        assert!(matches!(
+            load(Some(&path)),
+            Err(LoadError::Malformed { .. })
+        ));
+
+        let config: Config = serde_json::from_str(V1_CONFIG).unwrap();
+        save(&config, &path).unwrap();
+        let (loaded, source) = load(Some(&path)).unwrap();
+        assert_eq!(source, path);
+        assert_eq!(loaded.fingerprint().unwrap(), config.fingerprint().unwrap());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            // a planted symlink next to the config is never written through
+            let victim = dir.path().join("victim");
+            std::fs::write(&victim, "untouched").unwrap();
+            std::os::unix::fs::symlink(&victim, dir.path().join("hpos-config.json.tmp")).unwrap();
+            save(&config, &path).unwrap();
+            assert_eq!(std::fs::read_to_string(&victim).unwrap(), "untouched");
+        }
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..20 {
+                        save(&config, &path).unwrap();
+                    }
+                });
+            }
+        });
+        let (loaded, _) = load(Some(&path)).unwrap();
+        assert_eq!(loaded.fingerprint().unwrap(), config.fingerprint().unwrap());
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+            .filter(|name| name != "hpos-config.json.tmp")
+            .collect();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+    }
+
+    #[test]
+    fn sealed_config_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hpos-config.json");
+        let config: Config = serde_json::from_str(V1_CONFIG).unwrap();
+
+        save_sealed(&config, &path, &SealKey::Passphrase("correct horse")).unwrap();
+        let sealed = std::fs::read_to_string(&path).unwrap();
+        assert!(!sealed.contains("joel@holo.host"));
+        assert!(matches!(load(Some(&path)), Err(LoadError::Sealed { .. })));
+        assert!(matches!(
+            load_sealed(Some(&path), &UnsealKey::Passphrase("wrong")),
+            Err(LoadError::Unseal { .. })
+        ));
+        let (unsealed, _) =
+            load_sealed(Some(&path), &UnsealKey::Passphrase("correct horse")).unwrap();
+        assert_eq!(
+            unsealed.fingerprint().unwrap(),
+            config.fingerprint().unwrap()
+        );
+
+        // a crafted envelope can't make unsealing allocate 4 TiB
+        let mut envelope: serde_json::Value = serde_json::from_str(&sealed).unwrap();
+        envelope["sealed_v1"]["recipient"]["passphrase"]["memory_kib"] = u32::MAX.into();
+        let err = Config::unseal(
+            &envelope.to_string(),
+            &UnsealKey::Passphrase("correct horse"),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unsupported envelope key derivation"));
+
+        let device = SigningKey::from_bytes(&[3; 32]);
+        let sealed = config
+            .seal(&SealKey::Device(&device.verifying_key()))
+            .unwrap();
+        let other_device = SigningKey::from_bytes(&[4; 32]);
+        assert!(Config::unseal(&sealed, &UnsealKey::Device(&other_device)).is_err());
+        let unsealed = Config::unseal(&sealed, &UnsealKey::Device(&device)).unwrap();
+        assert_eq!(
+            unsealed.fingerprint().unwrap(),
+            config.fingerprint().unwrap()
+        );
+
+        // plain configs pass through
+        assert!(Config::unseal(V1_CONFIG, &UnsealKey::Device(&device)).is_ok());
+    }
+
+    #[test]
+    fn owner_signed_admin_changes() {
+        let device_pub_key = SigningKey::from_bytes(&[1; 32]).verifying_key();
+        let (config, _) = Config::new(
+            "owner@holo.host".into(),
+            "password".into(),
+            "registration-code".into(),
+            device_pub_key,
+            "1".into(),
+            "bundle".into(),
+            device_pub_key,
+        )
+        .unwrap();
+        let owner = admin_keypair_from(device_pub_key, "owner@holo.host", "password").unwrap();
+        let mut config = config.into_v4().unwrap();
+
+        let operator = SigningKey::from_bytes(&[5; 32]);
+        let add = AdminChange::Add(AdminIdentity {
+            email: "operator@holo.host".into(),
+            public_key: operator.verifying_key(),
+            role: AdminRole::Operator,
+            label: Some("night shift".into()),
+        });
+        let signed = add.clone().sign(&config, &operator).unwrap();
+        assert!(config.apply_admin_change(&signed).is_err());
+
+        let signed = add.sign(&config, &owner).unwrap();
+        config.apply_admin_change(&signed).unwrap();
+        // bound to the config it was signed for, so it can't be replayed
+        assert!(config.apply_admin_change(&signed).is_err());
+
+        let json = serde_json::to_string(&config).unwrap();
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.admins().len(), 2);
+        assert_eq!(config.email().unwrap(), "owner@holo.host");
+        assert_eq!(
+            config.admin_by_email("operator@holo.host").unwrap().role,
+            AdminRole::Operator
+        );
+
+        let mut config = config;
+        let remove_owner = AdminChange::Remove {
+            public_key: owner.verifying_key(),
+        }
+        .sign(&config, &owner)
+        .unwrap();
+        assert!(config.apply_admin_change(&remove_owner).is_err());
+
+        let remove_operator = AdminChange::Remove {
+            public_key: operator.verifying_key(),
+        }
+        .sign(&config, &owner)
+        .unwrap();
+        config.apply_admin_change(&remove_operator).unwrap();
+        assert!(config
+            .admin_by_public_key(&operator.verifying_key())
+            .is_none());
+
+        // only a hand built V4 config can lack an owner
+        if let Config::V4 { settings, .. } = &mut config {
+            settings.admins.clear();
+        }
+        assert!(config.email().is_none());
+        assert!(config.admin_public_key().is_none());
+        assert_eq!(config.summary(false).email, "");
+    }
+
+    #[test]
+    fn hosting_preferences_are_optional() {
+        let mut config: Config = serde_json::from_str(V1_CONFIG).unwrap();
+        assert!(config.hosting_preferences().is_none());
+        assert!(!serde_json::to_string(&config)
+            .unwrap()
+            .contains("hosting_preferences"));
+
+        let with_preferences = V1_CONFIG.replace(
+            r#""settings":{"#,
+            r#""settings":{"hosting_preferences":{"v1":{"price_compute":"0.5"}},"#,
+        );
+        let config_with: Config = serde_json::from_str(&with_preferences).unwrap();
+        let Some(HostingPreferences::V1(preferences)) = config_with.hosting_preferences() else {
+            panic!("expected v1 hosting preferences");
+        };
+        assert_eq!(preferences.price_compute, "0.5");
+        assert_eq!(
+            preferences.price_storage,
+            HostingPreferencesV1::default().price_storage
+        );
+        let changes = config.diff(&config_with);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ChangeKind::HostingPreferences);
+
+        // out of range values and newer versions are read, but don't validate
+        let invalid = V1_CONFIG.replace(
+            r#""settings":{"#,
+            r#""settings":{"hosting_preferences":{"v1":{"allowed_jurisdictions":["germany"]}},"#,
+        );
+        let config_invalid: Config = serde_json::from_str(&invalid).unwrap();
+        assert!(config_invalid
+            .hosting_preferences()
+            .unwrap()
+            .validate()
+            .is_err());
+        let newer = V1_CONFIG.replace(
+            r#""settings":{"#,
+            r#""settings":{"hosting_preferences":{"v2":{"price_compute":{"fuel":1}}},"#,
+        );
+        let config_newer: Config = serde_json::from_str(&newer).unwrap();
+        let preferences = config_newer.hosting_preferences().unwrap();
+        assert!(matches!(preferences, HostingPreferences::Unknown(_)));
+        assert!(preferences.validate().is_err());
+        assert!(serde_json::to_string(&config_newer)
+            .unwrap()
+            .contains(r#""hosting_preferences":{"v2":{"price_compute":{"fuel":1}}}"#));
+        assert!(config
+            .set_hosting_preferences(Some(preferences.clone()))
+            .is_err());
+
+        for price in ["-1", "1e3", ".5", "1.", "NaN", ""] {
+            let invalid = HostingPreferences::V1(HostingPreferencesV1 {
+                price_bandwidth: price.to_string(),
+                ..Default::default()
+            });
+            assert!(config.set_hosting_preferences(Some(invalid)).is_err());
+        }
+        let never_invoiced = HostingPreferences::V1(HostingPreferencesV1 {
+            max_time_before_invoice_secs: 0,
+            ..Default::default()
+        });
+        assert!(config
+            .set_hosting_preferences(Some(never_invoiced))
+            .is_err());
+        config
+            .set_hosting_preferences(Some(HostingPreferences::default()))
+            .unwrap();
+        // whole seconds and decimal strings, stable across releases
+        assert!(serde_json::to_string(&config).unwrap().contains(
+            r#""hosting_preferences":{"v1":{"max_time_before_invoice_secs":604800,"price_compute":"0.025","#
+        ));
+    }
+
+    #[test]
+    fn network_settings_are_validated() {
+        let mut config: Config = serde_json::from_str(V1_CONFIG).unwrap();
+        assert!(config.network().is_none());
+        assert!(!serde_json::to_string(&config).unwrap().contains("network"));
+
+        let network = NetworkSettings {
+            static_ip: Some(StaticIp {
+                address: "192.168.1.10/24".parse().unwrap(),
+                gateway: "192.168.1.1".parse().unwrap(),
+            }),
+            dns_servers: vec!["9.9.9.9".parse().unwrap()],
+            proxy: Some("socks5://proxy.local:1080".parse().unwrap()),
+            ..Default::default()
+        };
+        config.set_network(Some(network.clone())).unwrap();
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""address":"192.168.1.10/24""#));
+        let config: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.network(), Some(&network));
+
+        assert!("192.168.1.10".parse::<Cidr>().is_err());
+        assert!("192.168.1.10/33".parse::<Cidr>().is_err());
+        let mut config = config;
+        let outside_gateway = NetworkSettings {
+            static_ip: Some(StaticIp {
+                address: "192.168.1.10/24".parse().unwrap(),
+                gateway: "192.168.2.1".parse().unwrap(),
+            }),
+            ..Default::default()
+        };
+        assert!(config.set_network(Some(outside_gateway.clone())).is_err());
+
+        // malformed values fail the whole config
+        for (field, malformed) in [("address", "192.168.1.10"), ("gateway", "192.168.1")] {
+            let mut value = serde_json::to_value(&config).unwrap();
+            value["v1"]["settings"]["network"]["static_ip"][field] = malformed.into();
+            assert!(serde_json::from_value::<Config>(value).is_err());
+        }
+        let mut value = serde_json::to_value(&config).unwrap();
+        value["v1"]["settings"]["network"]["proxy"] = "not a url".into();
+        assert!(serde_json::from_value::<Config>(value).is_err());
+
+        // well formed but invalid settings are still readable, and only fail `validate`
+        let mut value = serde_json::to_value(&config).unwrap();
+        value["v1"]["settings"]["network"] = serde_json::to_value(&outside_gateway).unwrap();
+        let written: Config = serde_json::from_value(value).unwrap();
+        assert!(written.network().unwrap().validate().is_err());
+        let changes = config.diff(&written);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ChangeKind::Network);
+        let signal_over_http = NetworkSettings {
+            signal_url: Some("http://signal.holo.host".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(config.set_network(Some(signal_over_http.clone())).is_err());
+        let mut value = serde_json::to_value(&config).unwrap();
+        value["v1"]["settings"]["network"] = serde_json::to_value(&signal_over_http).unwrap();
+        let written: Config = serde_json::from_value(value).unwrap();
+        assert!(written.network().unwrap().validate().is_err());
+    }
+
+    #[test]
+    fn registration_payload_is_device_signed() {
+        let holoport_key = SigningKey::from_bytes(&[6; 32]);
+        let (config, _) = Config::new(
+            "joel@holo.host".into(),
+            "password".into(),
+            "registration-code".into(),
+            holoport_key.verifying_key(),
+            "1".into(),
+            "bundle".into(),
+            holoport_key.verifying_key(),
+        )
+        .unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let max_age = Duration::from_secs(60 * 60);
+
+        let signed = config.registration_payload(&holoport_key, now).unwrap();
+        assert_eq!(signed.payload.registration_code, "registration-code");
+        let json = serde_json::to_string(&signed).unwrap();
+        let signed: SignedRegistrationPayload = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            signed.verify(now, max_age).unwrap(),
+            holoport_key.verifying_key()
+        );
+        assert!(signed.verify(now + 2 * max_age, max_age).is_err());
+        assert!(signed.verify(now - max_age, max_age).is_err());
+
+        let mut tampered = signed.clone();
+        tampered.payload.registration_code = "other-code".into();
+        assert!(tampered.verify(now, max_age).is_err());
+
+        // a validly signed timestamp past what `SystemTime` holds
+        let mut far_future = signed.clone();
+        far_future.payload.timestamp = u64::MAX;
+        let mut message = b"hpos-config registration payload v1".to_vec();
+        message.extend(serde_json::to_vec(&far_future.payload).unwrap());
+        far_future.signature = holoport_key.sign(&message);
+        let err = far_future.verify(now, max_age).unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{}", err);
+
+        let other_key = SigningKey::from_bytes(&[7; 32]);
+        assert!(config.registration_payload(&other_key, now).is_err());
+        let v1: Config = serde_json::from_str(V1_CONFIG).unwrap();
+        assert!(v1.registration_payload(&holoport_key, now).is_err());
+    }
+
+    #[test]
+    fn conductor_setup_matches_golden_files() {
+        let holoport_key = SigningKey::from_bytes(&[8; 32]).verifying_key();
+        let (config, _) = Config::new(
+            "joel@holo.host".into(),
+            "password".into(),
+            "registration-code".into(),
+            holoport_key,
+            "1".into(),
+            "device-bundle".into(),
+            holoport_key,
+        )
+        .unwrap();
+        let setup = config
+            .conductor_setup(&ConductorOptions::default())
+            .unwrap();
+        assert_golden("conductor-config.yaml", &setup.conductor_config);
+        assert_golden("lair-import.sh", &setup.lair_import);
+
+        let mut config = config;
+        config
+            .set_network(Some(NetworkSettings {
+                bootstrap_url: Some("https://bootstrap.example.com".parse().unwrap()),
+                signal_url: Some("wss://signal.example.com".parse().unwrap()),
+                ..Default::default()
+            }))
+            .unwrap();
+        let options = ConductorOptions {
+            keystore_path: "/opt/it's here".into(),
+            admin_port: 4545,
+            ..Default::default()
+        };
+        let setup = config.conductor_setup(&options).unwrap();
+        assert_golden("conductor-config-custom.yaml", &setup.conductor_config);
+        assert_golden("lair-import-custom.sh", &setup.lair_import);
+
+        let v1: Config = serde_json::from_str(V1_CONFIG).unwrap();
+        assert!(v1.conductor_setup(&ConductorOptions::default()).is_err());
+    }
+
+    /// compare with tests/golden/`name`, run with UPDATE_GOLDEN=1 to rewrite it
+    fn assert_golden(name: &str, actual: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).unwrap();
+        }
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            expected, actual,
+            "{} is out of date, rerun with UPDATE_GOLDEN=1",
+            name
+        );
+    }
 }
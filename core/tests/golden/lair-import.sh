@@ -0,0 +1,6 @@
+#!/bin/sh
+# Generated by hpos-config for the HoloPort with agent key uhCAkE5j2LG0aRXxRumpLXz29L2n8qTIWIY3ImX5Ba9F9k8pkQ1le
+# Imports its device bundle into lair as seed 'hpos-host'. Reads the lair
+# passphrase and then the device bundle passphrase from stdin.
+set -e
+exec lair-keystore --lair-root '/var/lib/holochain-rsm/lair-keystore' import-seed --piped 'hpos-host' 'device-bundle'
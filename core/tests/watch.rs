@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+
+    use hpos_config_core::watch::{ConfigWatcher, WatchError, WatchEvent};
+    use hpos_config_core::{ChangeKind, Config, LoadError, OwnedUnsealKey, SealKey};
+    use std::time::Duration;
+
+    const V1_CONFIG: &str = r#"{"v1":{"seed":"BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc=","settings":{"admin":{"email":"joel@holo.host","public_key":"6kpsY+KcUgq+9VB7Ey7F+ZVHdq6+vnuSQh7qaRRG0iw"}}}}"#;
+
+    async fn next_event(watcher: &mut ConfigWatcher) -> WatchEvent {
+        tokio::time::timeout(Duration::from_secs(10), watcher.recv())
+            .await
+            .expect("no event before timeout")
+            .expect("watcher stopped")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn emits_reload_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hpos-config.json");
+        std::fs::write(&path, V1_CONFIG).unwrap();
+
+        let mut watcher = ConfigWatcher::new(&path, Duration::from_millis(100)).unwrap();
+        assert_eq!(watcher.config().email().unwrap(), "joel@holo.host");
+
+        std::fs::write(&path, V1_CONFIG.replace("joel@", "jane@")).unwrap();
+        match next_event(&mut watcher).await {
+            WatchEvent::Changed { config, changes } => {
+                assert_eq!(config.email().unwrap(), "jane@holo.host");
+                let kinds: Vec<_> = changes.iter().map(|change| change.kind).collect();
+                assert_eq!(kinds, vec![ChangeKind::Email]);
+            }
+            other => panic!("expected Changed, got {:?}", other),
+        }
+        assert_eq!(watcher.config().email().unwrap(), "jane@holo.host");
+
+        std::fs::write(&path, "{\"v1\":").unwrap();
+        match next_event(&mut watcher).await {
+            WatchEvent::Failed(LoadError::Malformed { .. }) => {}
+            other => panic!("expected Failed, got {:?}", other),
+        }
+        // a broken file doesn't replace the last good config
+        assert_eq!(watcher.config().email().unwrap(), "jane@holo.host");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn watches_sealed_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hpos-config.json");
+        let seal = |json: &str| {
+            let config: Config = serde_json::from_str(json).unwrap();
+            config.seal(&SealKey::Passphrase("passphrase")).unwrap()
+        };
+        std::fs::write(&path, seal(V1_CONFIG)).unwrap();
+
+        assert!(matches!(
+            ConfigWatcher::new(&path, Duration::from_millis(100)),
+            Err(WatchError::Load(LoadError::Sealed { .. }))
+        ));
+        let key = OwnedUnsealKey::Passphrase("passphrase".to_string().into());
+        let mut watcher =
+            ConfigWatcher::new_sealed(&path, Duration::from_millis(100), key).unwrap();
+
+        // re-sealing the same config changes every byte of the file but not the config
+        std::fs::write(&path, seal(V1_CONFIG)).unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        std::fs::write(&path, seal(&V1_CONFIG.replace("joel@", "jane@"))).unwrap();
+        match next_event(&mut watcher).await {
+            WatchEvent::Changed { config, changes } => {
+                assert_eq!(config.email().unwrap(), "jane@holo.host");
+                assert_eq!(changes.len(), 1);
+            }
+            other => panic!("expected Changed, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Prints the semantic differences between two hpos-config files, e.g. before
+//! and after a re-flash. Values are redacted like in `inspect`.
+//!
+//! Exits with status 1 when the configs differ, like diff(1).
+
+use anyhow::Result;
+use hpos_config_core::*;
+use std::path::PathBuf;
+use std::process;
+use structopt::StructOpt;
+
+fn main() -> Result<()> {
+    #[derive(StructOpt)]
+    struct Cli {
+        /// The path to the old hpos-config file
+        from: PathBuf,
+        /// The path to the new hpos-config file
+        to: PathBuf,
+        #[structopt(long = "json")]
+        /// Print the changes as JSON
+        json: bool,
+    }
+
+    let Cli { from, to, json } = Cli::from_args();
+
+    let (from, _) = load(Some(&from))?;
+    let (to, _) = load(Some(&to))?;
+    let changes = from.diff(&to);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else {
+        for change in &changes {
+            println!("{}", change);
+        }
+    }
+
+    if !changes.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -1,11 +1,17 @@
-use hpos_config_core::{config::Seed, public_key, Config};
+use hpos_config_core::{
+    config::{Seed, SEED_SIZE},
+    public_key,
+    registration::{RegistrationClient, Verification},
+    Config, HostingPreferences, HostingPreferencesV1, NetworkSettings, StaticIp,
+};
 
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser};
 use ed25519_dalek::*;
-use failure::Error;
+use failure::{bail, Error};
 use rand::Rng;
 use sha2::{Digest, Sha512Trunc256};
-use std::{fs::File, io};
+use std::{fs::File, io, net::IpAddr};
+use url::Url;
 
 #[derive(Parser, Clone)]
 #[command(about = "Creates HoloPortOS config file that contains seed and admin email/password.")]
@@ -54,20 +60,190 @@ struct ClapArgs {
         help = "Use SHA-512 hash of given file, truncated to 256 bits, as seed"
     )]
     seed_from: Option<String>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "DAYS",
+        help = "Hosting preference: longest time before usage is invoiced"
+    )]
+    max_time_before_invoice_days: Option<u64>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "DECIMAL",
+        help = "Hosting preference: HoloFuel per unit of CPU time, e.g. 0.025"
+    )]
+    price_compute: Option<String>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "DECIMAL",
+        help = "Hosting preference: HoloFuel per unit of storage, e.g. 0.025"
+    )]
+    price_storage: Option<String>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "DECIMAL",
+        help = "Hosting preference: HoloFuel per unit of bandwidth, e.g. 0.025"
+    )]
+    price_bandwidth: Option<String>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "CATEGORY",
+        help = "Hosting preference: hApp category to host, may be repeated"
+    )]
+    allowed_category: Vec<String>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "CODE",
+        help = "Hosting preference: ISO 3166-1 alpha-2 jurisdiction to host for, may be repeated"
+    )]
+    allowed_jurisdiction: Vec<String>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "CIDR",
+        requires = "gateway",
+        help = "Network: static address with prefix length, e.g. 192.168.1.10/24"
+    )]
+    static_ip: Option<String>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "IP",
+        requires = "static_ip",
+        help = "Network: default gateway for the static address"
+    )]
+    gateway: Option<IpAddr>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "IP",
+        help = "Network: DNS server overriding DHCP, may be repeated"
+    )]
+    dns: Vec<IpAddr>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "URL",
+        help = "Network: http, https or socks5 proxy"
+    )]
+    proxy: Option<Url>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "URL",
+        help = "Network: Holochain bootstrap server"
+    )]
+    bootstrap_url: Option<Url>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "URL",
+        help = "Network: Holochain signal server"
+    )]
+    signal_url: Option<Url>,
+    #[arg(
+        long,
+        value_parser,
+        value_name = "URL",
+        help = "Check the registration code with this membrane proof service first, e.g. https://hbs.holo.host"
+    )]
+    membrane_proof_service_url: Option<Url>,
+}
+
+impl ClapArgs {
+    /// `None` unless at least one hosting preference flag was given
+    fn hosting_preferences(&self) -> Result<Option<HostingPreferences>, clap::Error> {
+        let mut preferences = HostingPreferencesV1::default();
+        let mut any = false;
+        if let Some(days) = self.max_time_before_invoice_days {
+            preferences.max_time_before_invoice_secs =
+                days.checked_mul(24 * 60 * 60).ok_or_else(|| {
+                    ClapArgs::command().error(
+                        ErrorKind::ValueValidation,
+                        format!("--max-time-before-invoice-days {} is too large", days),
+                    )
+                })?;
+            any = true;
+        }
+        for (price, value) in [
+            (&mut preferences.price_compute, &self.price_compute),
+            (&mut preferences.price_storage, &self.price_storage),
+            (&mut preferences.price_bandwidth, &self.price_bandwidth),
+        ] {
+            if let Some(value) = value {
+                *price = value.clone();
+                any = true;
+            }
+        }
+        if !self.allowed_category.is_empty() {
+            preferences.allowed_categories = Some(self.allowed_category.clone());
+            any = true;
+        }
+        if !self.allowed_jurisdiction.is_empty() {
+            preferences.allowed_jurisdictions = Some(self.allowed_jurisdiction.clone());
+            any = true;
+        }
+        Ok(Some(HostingPreferences::V1(preferences)).filter(|_| any))
+    }
+
+    /// `None` unless at least one network flag was given
+    fn network(&self) -> Result<Option<NetworkSettings>, Error> {
+        let static_ip = match (&self.static_ip, self.gateway) {
+            (Some(address), Some(gateway)) => Some(StaticIp {
+                address: address.parse()?,
+                gateway,
+            }),
+            _ => None,
+        };
+        let network = NetworkSettings {
+            static_ip,
+            dns_servers: self.dns.clone(),
+            proxy: self.proxy.clone(),
+            bootstrap_url: self.bootstrap_url.clone(),
+            signal_url: self.signal_url.clone(),
+        };
+        Ok(Some(network).filter(|network| network != &NetworkSettings::default()))
+    }
+
+    /// Fail early on a code the service doesn't know, rather than when the
+    /// HoloPort first joins the network
+    fn verify_registration_code(&self) -> Result<(), Error> {
+        let service_url = match &self.membrane_proof_service_url {
+            Some(service_url) => service_url,
+            None => return Ok(()),
+        };
+        let client = RegistrationClient::new(service_url)?;
+        let verification = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(client.verify(&self.email, &self.registration_code))?;
+        match verification {
+            Verification::Valid => Ok(()),
+            Verification::Rejected(reason) => bail!("registration code rejected: {}", reason),
+        }
+    }
 }
 
 fn main() -> Result<(), Error> {
     let args = ClapArgs::parse();
+    let hosting_preferences = args.hosting_preferences().unwrap_or_else(|err| err.exit());
+    let network = args.network()?;
+    args.verify_registration_code()?;
 
     let seed = match args.seed_from {
-        None => rand::thread_rng().gen::<Seed>(),
+        None => Seed::from(rand::thread_rng().gen::<[u8; SEED_SIZE]>()),
         Some(path) => {
             let mut hasher = Sha512Trunc256::new();
             let mut file = File::open(path)?;
             io::copy(&mut file, &mut hasher)?;
 
-            let seed: Seed = hasher.result().into();
-            seed
+            let seed: [u8; SEED_SIZE] = hasher.result().into();
+            Seed::from(seed)
         }
     };
 
@@ -80,7 +256,7 @@ fn main() -> Result<(), Error> {
         }
     };
 
-    let (config, public_key) = Config::new(
+    let (mut config, public_key) = Config::new(
         args.email,
         args.password,
         args.registration_code,
@@ -89,6 +265,8 @@ fn main() -> Result<(), Error> {
         args.device_bundle,
         VerifyingKey::from(&secret_key),
     )?;
+    config.set_hosting_preferences(hosting_preferences)?;
+    config.set_network(network)?;
     eprintln!("{}", public_key::to_url(&public_key)?);
     println!("{}", serde_json::to_string_pretty(&config)?);
     Ok(())
@@ -0,0 +1,73 @@
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+/// Lets JS tell failures apart without parsing messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A public key is not 32 bytes or not a valid ed25519 point
+    InvalidPublicKey,
+    /// A config is not valid JSON of any config version
+    InvalidConfig,
+    /// Any other argument failed validation
+    InvalidArgument,
+    /// The config version does not support the operation
+    Unsupported,
+    /// A remote service could not be reached or failed
+    Service,
+    /// No master seed was generated, or it was zeroed
+    MissingSeed,
+    /// Bug or environment failure, not caused by the input
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidPublicKey => "invalid_public_key",
+            ErrorCode::InvalidConfig => "invalid_config",
+            ErrorCode::InvalidArgument => "invalid_argument",
+            ErrorCode::Unsupported => "unsupported",
+            ErrorCode::Service => "service",
+            ErrorCode::MissingSeed => "missing_seed",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+/// Error returned by every export. It reaches JS as an `Error` with an
+/// additional `code` property, so promises reject instead of the wasm
+/// instance aborting.
+#[derive(Debug)]
+pub struct WebError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl WebError {
+    pub fn new(code: ErrorCode, message: impl fmt::Display) -> Self {
+        WebError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<WebError> for JsValue {
+    fn from(err: WebError) -> JsValue {
+        let error = js_sys::Error::new(&err.message);
+        // setting a property on a fresh object can not fail
+        let _ = js_sys::Reflect::set(&error, &"code".into(), &err.code.as_str().into());
+        error.into()
+    }
+}
+
+/// Attach an `ErrorCode` to any displayable error
+pub trait WithCode<T> {
+    fn code(self, code: ErrorCode) -> Result<T, WebError>;
+}
+
+impl<T, E: fmt::Display> WithCode<T> for Result<T, E> {
+    fn code(self, code: ErrorCode) -> Result<T, WebError> {
+        self.map_err(|err| WebError::new(code, err))
+    }
+}
@@ -1,21 +1,21 @@
 
 (async () => {
   const filesaver = require('file-saver')
-  const hcSeedBundle = require('@holochain/hc-seed-bundle')
-  const { config } = await import('../pkg')
+  const {
+    verify_registration_code: verifyRegistrationCodeWithService,
+    config_file_name: genConfigFileName } = await import('../pkg')
   const {
     validateRegistrationCode,
     validateScreenSize,
     detectMobileUserAgent,
     validateEmail,
     validatePassphrae } = await import('./validation')
-  const { genConfigFileName, toBase64 } = await import('./utils')
+  const { startKeyWorker } = await import('./utils')
   const SEED_FILE_NAME = 'master-seed'
   const REVOCATION_KEY_FILE_NAME = 'revocation-key'
 
   const MEMBRANE_PROOF_SERVICE_URL = process.env.MEMBRANE_PROOF_SERVICE_URL
 
-  const REVOCATION_KEY_DEVICE_NUMBER = 0
   const HOLO_PORT_STARTING_DEVICE_NUMBER = 1
 
   let stepTracker = 0
@@ -24,8 +24,10 @@
   let downloadConfigTracker = false
   let downloadSeedTracker = false
   let configFileBlob = ''
-  let master
-  let revocation
+  // holds the unlocked master seed, see keys.worker.js
+  let keyWorker
+  // promise of the locked master seed and revocation key files
+  let seedFiles
   let deviceNumber = HOLO_PORT_STARTING_DEVICE_NUMBER
   let deviceID
   let genSeedStartingHtml
@@ -68,6 +70,7 @@
     passwordCheckInputArea: document.querySelector('#password-check-form-item'),
     formErrorMessage: document.querySelector('#form-error-message'),
     downloadFileName: document.querySelector('#download-file'),
+    loaderStage: document.querySelector('#loader-stage'),
   }
 
   const nextButtonLoaderColumn = document.querySelector('#next-button-loader-column')
@@ -89,10 +92,6 @@
     password: ''
   }
 
-  // global variable used to pass seed passphrase between steps 2 and 3
-  let seedPassphrase
-  let confirmPassphrase
-
   /** Actions executed at button click
   * ======================================
   */
@@ -127,8 +126,14 @@
             buttons.nextStep.disabled = true
             return
           }
-          seedPassphrase = inputs.seedPassphrase.value
-          confirmPassphrase = inputs.confirmPassphrase.value
+          // the passphrase is only handed to the key worker, which locks the
+          // seed files with it, and isn't kept on this page
+          keyWorker && keyWorker.zero()
+          keyWorker = startKeyWorker()
+          seedFiles = keyWorker.generateSeedFiles(inputs.seedPassphrase.value)
+          seedFiles.catch(() => {})
+          inputs.seedPassphrase.value = ''
+          inputs.confirmPassphrase.value = ''
 
           updateUiStep(3)
           updateProgressBar(2)
@@ -187,64 +192,19 @@
 
       setTimeout(async () => {
         try {
-          // setup bundler
-          await hcSeedBundle.seedBundleReady
-          // generate a new pure entropy master seed
-          // Note: we will clear the secret at exit of this app
-          master = hcSeedBundle.UnlockedSeedBundle.newRandom({
-            bundleType: 'master'
-          })
-
-          // we need the passphrase as a Uint8Array
-          const pw = (new TextEncoder()).encode(seedPassphrase)
-
-          const encodedBytes = master.lock([
-            new hcSeedBundle.SeedCipherPwHash(
-              hcSeedBundle.parseSecret(pw), 'minimum')
-          ])
-
-          // DEV MODE - check pub key for devices:
-          console.log("Created master seed: ", master.signPubKey)
-
-          const seedBlob = new Blob([toBase64(encodedBytes)], { type: 'text/plain' })
+          // a new pure entropy master seed and the revocation key derived from it
+          const { masterSeed, revocationKey } = await seedFiles
 
+          const seedBlob = new Blob([masterSeed], { type: 'text/plain' })
           filesaver.saveAs(seedBlob, SEED_FILE_NAME)
 
-        } catch (e) {
-          throw new Error(`Error saving config. Error: ${e}`)
-        }
-
-      }, 1000)
-
-      setTimeout(async () => {
-        try {
-          // setup bundler
-          await hcSeedBundle.seedBundleReady
-
-          // we need the passphrase as a Uint8Array
-          const pw = (new TextEncoder()).encode(seedPassphrase)
-
-          // clear passphrase from memory
-          seedPassphrase = null
-
-          revocation = master.derive(REVOCATION_KEY_DEVICE_NUMBER, {
-            bundleType: 'revocation'
-          })
-
-          const revocationBytes = revocation.lock([
-            new hcSeedBundle.SeedCipherPwHash(
-              hcSeedBundle.parseSecret(pw), 'minimum')
-          ])
-
-          // DEV MODE - check pub key for devices:
-          console.log("Created revocation seed: ", revocation.signPubKey)
-
-          const revocationBlob = new Blob([toBase64(revocationBytes)], { type: 'text/plain' })
+          // browsers may drop a download started right after another one
+          await new Promise(resolve => setTimeout(resolve, 1000))
 
+          const revocationBlob = new Blob([revocationKey], { type: 'text/plain' })
           filesaver.saveAs(revocationBlob, REVOCATION_KEY_FILE_NAME)
-
         } catch (e) {
-          throw new Error(`Error saving revocation key. Error: ${e}`)
+          throw new Error(`Error saving seed and revocation key. Error: ${e}`)
         }
 
         /* Clean State */
@@ -252,7 +212,7 @@
         buttons.genSeed.disabled = true
         buttons.genSeed.innerHTML = 'Saved Seed & Key Files'
         verifySeedDownloadComplete(downloadSeedTracker)
-      }, 2000)
+      }, 1000)
     },
     download: async () => {
       /* Communicate visually that something is happening in the background */
@@ -281,6 +241,17 @@
     closeLoader: () => {
       document.querySelector('#fixed-overlay-loader').style.display = 'none'
       document.querySelector('#modal-overlay-loader').style.display = 'none'
+      inlineVariables.loaderStage.innerHTML = ''
+    },
+    updateLoaderStage: stage => {
+      const stages = {
+        loading: 'Preparing key derivation',
+        deriving_device_root: 'Deriving your HoloPort keys',
+        locking_device_bundle: 'Locking your HoloPort keys',
+        deriving_admin_key: 'Deriving your admin key, this can take a while',
+        done: 'Saving your keys'
+      }
+      inlineVariables.loaderStage.innerHTML = stages[stage]
     },
     loadNextStep: async promise => {
       nextButtonLoaderColumn.classList.add('loading')
@@ -328,7 +299,9 @@
       downloadConfigTracker = false
       downloadSeedTracker = false
       configFileBlob = ''
-      master = undefined
+      keyWorker && keyWorker.zero()
+      keyWorker = undefined
+      seedFiles = undefined
       deviceNumber = HOLO_PORT_STARTING_DEVICE_NUMBER
       deviceID = undefined
       updateProgressBar(3, rewind)
@@ -336,7 +309,9 @@
     },
     exit: () => {
       // clear our secrets
-      master && master.zero()
+      keyWorker && keyWorker.zero()
+      keyWorker = undefined
+      seedFiles = undefined
       updateUiStep(-1)
     },
     loop: () => {
@@ -535,29 +510,7 @@
   // with an invalid registration code. The purpose is simply to prevent users from wasting time setting up a
   // HoloPort with the wrong code.
   const verifyRegistrationCode = async ({ registration_code, email }) => {
-    const response = await fetch(`${MEMBRANE_PROOF_SERVICE_URL}/registration/api/v1/verify-registration-code`,
-    {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json'
-      },
-      body: JSON.stringify({
-        role: 'host',
-        email: email,
-        code: registration_code
-      })
-    })
-    if (response.status === 201 || response.status === 200) {
-      return true
-    }
-    if (response.status !== 500) {
-      throw new Error(`Service responded with status code ${response.status}`)
-    }
-    const body = await response.json()
-    if (!body.isDisplayedToUser) {
-      throw new Error(`Received error response from service: ${body.error}: ${body.info}`)
-    }
-    return body.info
+    return verifyRegistrationCodeWithService(MEMBRANE_PROOF_SERVICE_URL, email, registration_code)
   }
 
   const generate = async () => {
@@ -574,35 +527,21 @@
     downloadConfigTracker = false
     click.openLoader()
 
-    setTimeout(() => {
+    setTimeout(async () => {
       try {
         inlineVariables.formErrorMessage.innerHTML = ''
-        // generate device bundle
-        // derive a device root seed from the master
-        const deviceRoot = master.derive(deviceNumber, {
-          bundleType: 'deviceRoot'
-        })
-
-        // encrypts it with password: pass
-        let pubKey = deviceRoot.signPubKey
-        const pw = (new TextEncoder()).encode('pass')
-        const encodedBytes = deviceRoot.lock([
-          new hcSeedBundle.SeedCipherPwHash(
-            hcSeedBundle.parseSecret(pw), 'minimum')
-        ])
-
-        // pass seed into the blob
-        let seed = {
-          derivationPath: deviceNumber,
-          // base64 encode it URLSAFE_NO_PADDING
-          deviceRoot: toBase64(encodedBytes),
-          pubKey
-        }
-        // Generate hpos-config.json and create download blob attached to url
-        generateBlob(user, seed)
-        // clear our secrets
-        deviceRoot.zero()
+        // derive the device bundle from the master seed and build hpos-config.json
+        // in the key worker, hashing the admin password would freeze the loader
+        const configData = await keyWorker.deriveConfig({
+          deviceNumber,
+          email: user.email,
+          password: user.password,
+          registrationCode: user.registrationCode
+        }, click.updateLoaderStage)
+        // create download blob attached to url
+        generateBlob(configData)
       } catch (e) {
+        click.closeLoader()
         inlineVariables.formErrorMessage.innerHTML = errorMessages.generateConfig
         throw new Error(`Error executing generateBlob with an error.  Error: ${e}`)
       }
@@ -620,24 +559,10 @@
   /**
    * Generate save link of hpos-config.json and attach to `button` domElement
    *
-   * @param {Object} user
-   * @param {Object} seed {derivationPath, deviceRoot, pubKey}
+   * @param {Object} configData {config, fingerprint, id, url}, from keyWorker.deriveConfig
   */
-  const generateBlob = (user, seed) => {
-
-    let configBlob
-    let configData
-
-    try {
-      const derivationPath = seed.derivationPath.toString()
-
-
-      configData = config(user.email, user.password, user.registrationCode, revocation.signPubKey, derivationPath, seed.deviceRoot, seed.pubKey)
-      configBlob = new Blob([configData.config], { type: 'application/json' })
-    } catch (e) {
-      inlineVariables.formErrorMessage.innerHTML = errorMessages.generateConfig
-      throw new Error(`Error executing generateBlob with an error.  Error: ${e}`)
-    }
+  const generateBlob = configData => {
+    const configBlob = new Blob([configData.config], { type: 'application/json' })
 
     /* NB: Do not delete!  Keep the below in case we decide to use the HoloPort url it is available right here */
     // console.log('Optional HoloPort url : ', configData.url)
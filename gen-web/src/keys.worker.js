@@ -0,0 +1,44 @@
+/**
+ * Holds Quickstart's unlocked master seed for as long as the page needs it,
+ * so the seed passphrase is only used once and never kept around. The seed
+ * bundles and the config are built in wasm (`src/seed.rs`), which keeps the
+ * master seed in this worker's instance. The Argon2id hash of the admin
+ * password takes seconds (much longer in unoptimized builds) and would
+ * otherwise freeze the UI.
+ *
+ * Receives one message at a time and answers with { error: { message, code } }
+ * on failure:
+ *  - { generate: { passphrase } } creates the master seed and its revocation
+ *    key, both locked with the passphrase, and answers { seedFiles: { masterSeed, revocationKey } }
+ *  - { config: { deviceNumber, email, password, registrationCode } } derives
+ *    the device bundle, posts a { stage } update as each step starts, and
+ *    answers { configData }
+ *  - { zero: true } wipes the master seed and closes the worker
+ */
+
+const generate = async ({ passphrase }) => {
+  const { generate_seed_files: generateSeedFiles } = await import('../pkg')
+  const { master_seed: masterSeed, revocation_key: revocationKey } = await generateSeedFiles(passphrase)
+  return { seedFiles: { masterSeed, revocationKey } }
+}
+
+const deriveConfig = async ({ deviceNumber, email, password, registrationCode }) => {
+  self.postMessage({ stage: 'loading' })
+  const { config_from_master_seed: configFromMasterSeed } = await import('../pkg')
+  const configData = await configFromMasterSeed(deviceNumber, email, password, registrationCode, undefined, stage => self.postMessage({ stage }))
+  self.postMessage({ stage: 'done' })
+  return { configData }
+}
+
+self.onmessage = async ({ data }) => {
+  if (data.zero) {
+    const { zero_master_seed: zeroMasterSeed } = await import('../pkg')
+    zeroMasterSeed()
+    return self.close()
+  }
+  try {
+    self.postMessage(data.generate ? await generate(data.generate) : await deriveConfig(data.config))
+  } catch (e) {
+    self.postMessage({ error: { message: e.message || `${e}`, code: e.code || 'internal' } })
+  }
+}
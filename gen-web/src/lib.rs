@@ -1,18 +1,77 @@
+mod error;
+pub mod seed;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use ed25519_dalek::VerifyingKey;
-use failure::Error;
-use hpos_config_core::{public_key, Config};
+use hpos_config_core::registration::{RegistrationClient, Verification};
+use hpos_config_core::{admin_keypair_from, public_key, Config, HostingPreferences};
 use serde::*;
 use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use error::WithCode;
+pub use error::{ErrorCode, WebError};
 
 #[derive(Serialize)]
 pub struct ConfigData {
     config: String,
+    fingerprint: String,
+    id: String,
+    url: String,
+}
+
+#[derive(Serialize)]
+pub struct HoloportIds {
     id: String,
     url: String,
+    agent_key: String,
+}
+
+#[derive(Serialize)]
+pub struct MigratedConfig {
+    config: String,
+    fingerprint: String,
+}
+
+const CONFIG_FILE_PREFIX: &str = "hp-config";
+
+thread_local! {
+    /// Master seed of the last `generate_seed_files`, unlocked until
+    /// `zero_master_seed`. Wasm is single threaded, so this is the instance's
+    /// only copy, and it never leaves the worker that loaded the module.
+    static MASTER_SEED: RefCell<Option<Rc<seed::MasterSeed>>> = const { RefCell::new(None) };
 }
 
-// https://github.com/rustwasm/wasm-bindgen/issues/1004
-fn config_raw(
+/// Log panics to the console instead of an opaque `unreachable` trap. No
+/// export is expected to panic, every failure is returned as a `WebError`.
+#[wasm_bindgen(start)]
+pub fn start() {
+    console_error_panic_hook::set_once();
+}
+
+fn public_key_from(bytes: Vec<u8>, name: &str) -> Result<VerifyingKey, WebError> {
+    let bytes: [u8; 32] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+        WebError::new(
+            ErrorCode::InvalidPublicKey,
+            format!("{} must be 32 bytes, got {}", name, bytes.len()),
+        )
+    })?;
+    VerifyingKey::from_bytes(&bytes).code(ErrorCode::InvalidPublicKey)
+}
+
+fn parse(config: &str) -> Result<Config, WebError> {
+    serde_json::from_str(config).code(ErrorCode::InvalidConfig)
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, WebError> {
+    serde_wasm_bindgen::to_value(value).code(ErrorCode::Internal)
+}
+
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn config(
     email: String,
     password: String,
     registration_code: String,
@@ -20,18 +79,10 @@ fn config_raw(
     derivation_path: String,
     device_bundle: String,
     device_pub_key: Vec<u8>,
-) -> Result<JsValue, Error> {
-    let device_pub_key: VerifyingKey = VerifyingKey::from_bytes(
-        &device_pub_key
-            .try_into()
-            .expect("Expected a Vec of length 32"),
-    )?;
-
-    let revocation_pub_key = VerifyingKey::from_bytes(
-        &revocation_pub_key
-            .try_into()
-            .expect("Expected a Vec of length 32"),
-    )?;
+    hosting_preferences: JsValue,
+) -> Result<JsValue, WebError> {
+    let device_pub_key = public_key_from(device_pub_key, "device public key")?;
+    let revocation_pub_key = public_key_from(revocation_pub_key, "revocation public key")?;
 
     let (config, public_key) = Config::new(
         email,
@@ -41,37 +92,175 @@ fn config_raw(
         derivation_path,
         device_bundle,
         device_pub_key,
-    )?;
+    )
+    .code(ErrorCode::Internal)?;
 
-    let config_data = ConfigData {
-        config: serde_json::to_string_pretty(&config)?,
+    config_data(config, public_key, hosting_preferences)
+}
+
+fn config_data(
+    mut config: Config,
+    public_key: VerifyingKey,
+    hosting_preferences: JsValue,
+) -> Result<JsValue, WebError> {
+    // optional, so existing callers can leave it out
+    if !hosting_preferences.is_undefined() && !hosting_preferences.is_null() {
+        let hosting_preferences: HostingPreferences =
+            serde_wasm_bindgen::from_value(hosting_preferences).code(ErrorCode::InvalidArgument)?;
+        config
+            .set_hosting_preferences(Some(hosting_preferences))
+            .code(ErrorCode::InvalidArgument)?;
+    }
+
+    to_js(&ConfigData {
+        config: serde_json::to_string_pretty(&config).code(ErrorCode::Internal)?,
+        fingerprint: config.fingerprint().code(ErrorCode::Internal)?,
         id: public_key::to_base36_id(&public_key),
-        url: public_key::to_url(&public_key)?.to_string(),
-    };
+        url: public_key::to_url(&public_key)
+            .code(ErrorCode::Internal)?
+            .to_string(),
+    })
+}
 
-    Ok(JsValue::from_serde(&config_data)?)
+/// Generate a random master seed and its revocation key, see
+/// `seed::MasterSeed::generate`. Resolves to `{ master_seed, revocation_key }`,
+/// the contents of the two files the user saves. The unlocked master seed is
+/// kept for `config_from_master_seed`, replacing any previous one.
+#[wasm_bindgen]
+pub async fn generate_seed_files(passphrase: String) -> Result<JsValue, WebError> {
+    let passphrase = Zeroizing::new(passphrase);
+    let (master_seed, seed_files) = seed::MasterSeed::generate(&passphrase).await?;
+    MASTER_SEED.with(|kept| *kept.borrow_mut() = Some(Rc::new(master_seed)));
+    to_js(&seed_files)
 }
 
+/// Derive the device bundle from the kept master seed and build the config,
+/// see `seed::MasterSeed::derive_config`. Resolves to the same data as
+/// `config`.
+///
+/// `on_stage` is called with the name of each stage as it starts. The admin
+/// key derivation at the end blocks for seconds, so Quickstart runs this in
+/// `keys.worker.js`.
 #[wasm_bindgen]
-pub fn config(
+pub async fn config_from_master_seed(
+    device_number: u32,
     email: String,
     password: String,
     registration_code: String,
-    revocation_pub_key: Vec<u8>,
-    derivation_path: String,
-    device_bundle: String,
-    device_pub_key: Vec<u8>,
-) -> Result<JsValue, JsValue> {
-    match config_raw(
-        email,
-        password,
-        registration_code,
-        revocation_pub_key,
-        derivation_path,
-        device_bundle,
-        device_pub_key,
-    ) {
-        Ok(js_val) => Ok(js_val),
-        Err(e) => Err(e.to_string().into()),
+    hosting_preferences: JsValue,
+    on_stage: Option<js_sys::Function>,
+) -> Result<JsValue, WebError> {
+    let master_seed = MASTER_SEED
+        .with(|kept| kept.borrow().clone())
+        .ok_or_else(|| {
+            WebError::new(
+                ErrorCode::MissingSeed,
+                "no master seed, call generate_seed_files first",
+            )
+        })?;
+    let report = |stage: seed::Stage| {
+        if let Some(on_stage) = &on_stage {
+            // a throwing callback must not abort the derivation
+            let _ = on_stage.call1(&JsValue::NULL, &stage.as_str().into());
+        }
+    };
+    let (config, public_key) = master_seed
+        .derive_config(device_number, email, password, registration_code, report)
+        .await?;
+
+    config_data(config, public_key, hosting_preferences)
+}
+
+/// Drop the kept master seed, wiping its secrets once no derivation is
+/// using it
+#[wasm_bindgen]
+pub fn zero_master_seed() {
+    MASTER_SEED.with(|kept| kept.borrow_mut().take());
+}
+
+/// Check a registration code with the membrane proof service. Resolves to
+/// `true` for a valid code, or to the reason to show the user if the service
+/// rejected it.
+#[wasm_bindgen]
+pub async fn verify_registration_code(
+    service_url: String,
+    email: String,
+    registration_code: String,
+) -> Result<JsValue, WebError> {
+    let service_url = url::Url::parse(&service_url).code(ErrorCode::InvalidArgument)?;
+    let client = RegistrationClient::new(&service_url).code(ErrorCode::InvalidArgument)?;
+    match client
+        .verify(&email, &registration_code)
+        .await
+        .code(ErrorCode::Service)?
+    {
+        Verification::Valid => Ok(JsValue::TRUE),
+        Verification::Rejected(reason) => Ok(reason.into()),
     }
 }
+
+/// Stable fingerprint of a serialized config, see `Config::fingerprint`
+#[wasm_bindgen]
+pub fn fingerprint(config: String) -> Result<String, WebError> {
+    parse(&config)?.fingerprint().code(ErrorCode::Internal)
+}
+
+/// Default hosting preferences, to prefill the Quickstart form
+#[wasm_bindgen]
+pub fn default_hosting_preferences() -> Result<JsValue, WebError> {
+    to_js(&HostingPreferences::default())
+}
+
+/// Validate a serialized config and return its redacted summary
+#[wasm_bindgen]
+pub fn parse_config(config: String) -> Result<JsValue, WebError> {
+    to_js(&parse(&config)?.summary(false))
+}
+
+/// Holoport id, url and holochain agent key of a device public key
+#[wasm_bindgen]
+pub fn holoport_ids(device_pub_key: Vec<u8>) -> Result<JsValue, WebError> {
+    let device_pub_key = public_key_from(device_pub_key, "device public key")?;
+    to_js(&HoloportIds {
+        id: public_key::to_base36_id(&device_pub_key),
+        url: public_key::to_url(&device_pub_key)
+            .code(ErrorCode::Internal)?
+            .to_string(),
+        agent_key: public_key::to_holochain_encoded_agent_key(&device_pub_key),
+    })
+}
+
+/// Base64 admin public key for logging in with email and password, as
+/// stored in the config settings. Runs a 64 MiB Argon2id hash that blocks
+/// for seconds, call it from a Web Worker.
+#[wasm_bindgen]
+pub fn admin_public_key(
+    email: String,
+    password: String,
+    device_pub_key: Vec<u8>,
+) -> Result<String, WebError> {
+    let device_pub_key = public_key_from(device_pub_key, "device public key")?;
+    let admin_keypair =
+        admin_keypair_from(device_pub_key, &email, &password).code(ErrorCode::Internal)?;
+    Ok(base64::encode_config(
+        admin_keypair.verifying_key().to_bytes(),
+        base64::STANDARD_NO_PAD,
+    ))
+}
+
+/// Upgrade a serialized config to the latest version, see `Config::into_v4`
+#[wasm_bindgen]
+pub fn migrate_config(config: String) -> Result<JsValue, WebError> {
+    let config = parse(&config)?.into_v4().code(ErrorCode::Unsupported)?;
+    to_js(&MigratedConfig {
+        config: serde_json::to_string_pretty(&config).code(ErrorCode::Internal)?,
+        fingerprint: config.fingerprint().code(ErrorCode::Internal)?,
+    })
+}
+
+/// File name Quickstart saves the config of a holoport under
+#[wasm_bindgen]
+pub fn config_file_name(holoport_id: String) -> String {
+    let prefix: String = holoport_id.chars().take(5).collect();
+    format!("{}-{}.json", CONFIG_FILE_PREFIX, prefix)
+}
@@ -0,0 +1,161 @@
+//! Quickstart's key derivation, formerly done in `index.js` with
+//! `@holochain/hc-seed-bundle`. Doing it here with the `hc_seed_bundle` crate
+//! keeps a single implementation that the Rust tests cover.
+//!
+//! The master seed derives the revocation key at device number 0 and device
+//! roots from 1 on. The device root's signing key is the holoport key.
+//! `MasterSeed` keeps the master unlocked after generating it, so the seed
+//! passphrase is only needed once and never has to be kept around.
+
+use ed25519_dalek::VerifyingKey;
+use hc_seed_bundle::{PwHashLimits, UnlockedSeedBundle};
+use hpos_config_core::Config;
+use serde::*;
+
+use crate::error::{ErrorCode, WebError, WithCode};
+
+pub const REVOCATION_KEY_DEVICE_NUMBER: u32 = 0;
+pub const HOLOPORT_STARTING_DEVICE_NUMBER: u32 = 1;
+
+/// HPOS unlocks the device bundle at boot without user input
+pub const DEVICE_BUNDLE_PASSPHRASE: &str = "pass";
+
+/// Locked seed bundles the user saves before the config is generated, both
+/// base64 url safe without padding
+#[derive(Serialize)]
+pub struct SeedFiles {
+    pub master_seed: String,
+    pub revocation_key: String,
+}
+
+/// Steps of `MasterSeed::derive_config`, reported so the UI can show what is happening
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    DerivingDeviceRoot,
+    LockingDeviceBundle,
+    DerivingAdminKey,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::DerivingDeviceRoot => "deriving_device_root",
+            Stage::LockingDeviceBundle => "locking_device_bundle",
+            Stage::DerivingAdminKey => "deriving_admin_key",
+        }
+    }
+}
+
+/// Bundle app data as written by `@holochain/hc-seed-bundle`
+#[derive(Serialize)]
+struct AppData {
+    #[serde(rename = "bundleType")]
+    bundle_type: &'static str,
+}
+
+/// An unlocked master seed, its secrets are wiped when it is dropped
+pub struct MasterSeed {
+    master: UnlockedSeedBundle,
+}
+
+impl MasterSeed {
+    /// Generate a random master seed and the files the user saves, the
+    /// master seed and the revocation key derived from it, both locked with
+    /// `passphrase`
+    pub async fn generate(passphrase: &str) -> Result<(MasterSeed, SeedFiles), WebError> {
+        let mut master = UnlockedSeedBundle::new_random()
+            .await
+            .code(ErrorCode::Internal)?;
+        set_bundle_type(&mut master, "master")?;
+        let master_seed = lock(&master, passphrase).await?;
+
+        let mut revocation = master
+            .derive(REVOCATION_KEY_DEVICE_NUMBER)
+            .await
+            .code(ErrorCode::Internal)?;
+        set_bundle_type(&mut revocation, "revocation")?;
+        let revocation_key = lock(&revocation, passphrase).await?;
+
+        Ok((
+            MasterSeed { master },
+            SeedFiles {
+                master_seed,
+                revocation_key,
+            },
+        ))
+    }
+
+    /// Derive the revocation key and the device root at `device_number`, and
+    /// build the config around the device bundle
+    pub async fn derive_config(
+        &self,
+        device_number: u32,
+        email: String,
+        password: String,
+        registration_code: String,
+        on_stage: impl Fn(Stage),
+    ) -> Result<(Config, VerifyingKey), WebError> {
+        if device_number < HOLOPORT_STARTING_DEVICE_NUMBER {
+            return Err(WebError::new(
+                ErrorCode::InvalidArgument,
+                format!(
+                    "device number must be at least {}, {} derives the revocation key",
+                    HOLOPORT_STARTING_DEVICE_NUMBER, REVOCATION_KEY_DEVICE_NUMBER
+                ),
+            ));
+        }
+
+        on_stage(Stage::DerivingDeviceRoot);
+        let revocation = self
+            .master
+            .derive(REVOCATION_KEY_DEVICE_NUMBER)
+            .await
+            .code(ErrorCode::Internal)?;
+        let mut device_root = self
+            .master
+            .derive(device_number)
+            .await
+            .code(ErrorCode::Internal)?;
+        set_bundle_type(&mut device_root, "deviceRoot")?;
+
+        on_stage(Stage::LockingDeviceBundle);
+        let device_bundle = lock(&device_root, DEVICE_BUNDLE_PASSPHRASE).await?;
+
+        on_stage(Stage::DerivingAdminKey);
+        Config::new(
+            email,
+            password,
+            registration_code,
+            sign_pub_key(&revocation)?,
+            device_number.to_string(),
+            device_bundle,
+            sign_pub_key(&device_root)?,
+        )
+        .code(ErrorCode::Internal)
+    }
+}
+
+fn set_bundle_type(
+    bundle: &mut UnlockedSeedBundle,
+    bundle_type: &'static str,
+) -> Result<(), WebError> {
+    bundle
+        .set_app_data(&AppData { bundle_type })
+        .code(ErrorCode::Internal)
+}
+
+fn sign_pub_key(bundle: &UnlockedSeedBundle) -> Result<VerifyingKey, WebError> {
+    let bytes: [u8; 32] = *bundle.get_sign_pub_key().read_lock();
+    VerifyingKey::from_bytes(&bytes).code(ErrorCode::Internal)
+}
+
+/// Lock with the `minimum` pwhash limits Quickstart has always used, so the
+/// bundle unlocks on a HoloPort within seconds
+async fn lock(bundle: &UnlockedSeedBundle, passphrase: &str) -> Result<String, WebError> {
+    let passphrase = sodoken::BufRead::from(passphrase.as_bytes().to_vec());
+    let cipher = PwHashLimits::Minimum
+        .with_exec(|| bundle.lock().add_pwhash_cipher(passphrase).lock())
+        .await
+        .code(ErrorCode::Internal)?;
+    Ok(base64::encode_config(&cipher, base64::URL_SAFE_NO_PAD))
+}
@@ -4,26 +4,44 @@
  * /
 
 /**
- *  converts encoded bytes to base64 on browser
- * @param {array} encodedBytes
+ *  starts the Web Worker that holds the unlocked master seed, see keys.worker.js.
+ *  Requests are answered one at a time, in order.
  */
+export const startKeyWorker = () => {
+    const worker = new Worker(new URL('./keys.worker.js', import.meta.url))
+    let queue = Promise.resolve()
 
-import _sodium from 'libsodium-wrappers'
+    const request = (message, onStage = () => {}) => {
+        const answer = queue.then(() => new Promise((resolve, reject) => {
+            worker.onmessage = ({ data }) => {
+                if (data.stage) return onStage(data.stage)
+                if (data.error) {
+                    const error = new Error(data.error.message)
+                    error.code = data.error.code
+                    return reject(error)
+                }
+                resolve(data)
+            }
+            worker.onerror = (e) => reject(new Error(e.message))
+            worker.postMessage(message)
+        }))
+        queue = answer.catch(() => {})
+        return answer
+    }
 
-export const toBase64 = (encodedBytes) => {
-    return _sodium.to_base64(encodedBytes, _sodium.base64_variants.URLSAFE_NO_PADDING)
+    return {
+        /**
+         * @param {String} passphrase only passed on to the worker, which locks both files with it
+         * @returns {Promise<Object>} {masterSeed, revocationKey}, base64 locked seed bundles
+         */
+        generateSeedFiles: async passphrase => (await request({ generate: { passphrase } })).seedFiles,
+        /**
+         * @param {Object} keys {deviceNumber, email, password, registrationCode}
+         * @param {function} onStage called with the name of each step as the worker starts it
+         * @returns {Promise<Object>} {config, fingerprint, id, url}
+         */
+        deriveConfig: async (keys, onStage) => (await request({ config: keys }, onStage)).configData,
+        /** wipes the master seed, the worker can't be used afterwards */
+        zero: () => worker.postMessage({ zero: true })
+    }
 }
-
-
-const FILE_PREFIX = "hp-config"
-const FILE_TYPE = ".json"
-
-/**	
- * generate file name based on the device number
- * @param {number} deviceNumber
- * @param {string} pubKey	
- */
-export const genConfigFileName = (pubKey) => {
-    return `${FILE_PREFIX}-${pubKey.substring(0, 5)}${FILE_TYPE}`
-
-}
\ No newline at end of file
@@ -0,0 +1,98 @@
+//! The derivation behind Quickstart, run natively
+#![cfg(not(target_arch = "wasm32"))]
+
+use hc_seed_bundle::{LockedSeedCipher, UnlockedSeedBundle};
+use hpos_config_core::{public_key, Config};
+use hpos_config_gen_web::{seed, ErrorCode};
+use std::cell::RefCell;
+
+async fn unlock(locked: &str, passphrase: &str) -> UnlockedSeedBundle {
+    let cipher = base64::decode_config(locked, base64::URL_SAFE_NO_PAD).unwrap();
+    match UnlockedSeedBundle::from_locked(&cipher)
+        .await
+        .unwrap()
+        .remove(0)
+    {
+        LockedSeedCipher::PwHash(cipher) => cipher
+            .unlock(sodoken::BufRead::from(passphrase.as_bytes().to_vec()))
+            .await
+            .unwrap(),
+        _ => panic!("expected a pwhash cipher"),
+    }
+}
+
+fn sign_pub_key(bundle: &UnlockedSeedBundle) -> Vec<u8> {
+    bundle.get_sign_pub_key().read_lock().to_vec()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn derives_config_from_master_seed() {
+    let (master_seed, files) = seed::MasterSeed::generate("seed passphrase").await.unwrap();
+
+    let stages = RefCell::new(Vec::new());
+    let (config, holoport_key) = master_seed
+        .derive_config(
+            2,
+            "pj@abba.pl".into(),
+            "password".into(),
+            "registration code".into(),
+            |stage| stages.borrow_mut().push(stage),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        stages.into_inner(),
+        [
+            seed::Stage::DerivingDeviceRoot,
+            seed::Stage::LockingDeviceBundle,
+            seed::Stage::DerivingAdminKey,
+        ]
+    );
+
+    let master = unlock(&files.master_seed, "seed passphrase").await;
+    let revocation = unlock(&files.revocation_key, "seed passphrase").await;
+    assert_eq!(
+        sign_pub_key(&revocation),
+        sign_pub_key(&master.derive(0).await.unwrap())
+    );
+
+    match &config {
+        Config::V3 {
+            device_bundle,
+            device_derivation_path,
+            revocation_pub_key,
+            holoport_id,
+            ..
+        } => {
+            let device_root = unlock(device_bundle, seed::DEVICE_BUNDLE_PASSPHRASE).await;
+            assert_eq!(
+                sign_pub_key(&device_root),
+                sign_pub_key(&master.derive(2).await.unwrap())
+            );
+            assert_eq!(sign_pub_key(&device_root), holoport_key.to_bytes());
+            assert_eq!(holoport_id, &public_key::to_base36_id(&holoport_key));
+            assert_eq!(device_derivation_path, "2");
+            assert_eq!(
+                revocation_pub_key.to_bytes().to_vec(),
+                sign_pub_key(&revocation)
+            );
+        }
+        _ => panic!("expected a v3 config"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_revocation_device_number() {
+    let (master_seed, _) = seed::MasterSeed::generate("seed passphrase").await.unwrap();
+    let err = master_seed
+        .derive_config(
+            seed::REVOCATION_KEY_DEVICE_NUMBER,
+            "pj@abba.pl".into(),
+            "password".into(),
+            "registration code".into(),
+            |_| (),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.code, ErrorCode::InvalidArgument);
+}
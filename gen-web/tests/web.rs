@@ -0,0 +1,180 @@
+//! Every bad input must reject with a coded `Error` instead of trapping.
+//! Run with `wasm-pack test --node gen-web`.
+#![cfg(target_arch = "wasm32")]
+
+use hpos_config_gen_web::*;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+const DEVICE_PUB_KEY: [u8; 32] = [
+    215, 90, 152, 1, 130, 177, 10, 183, 213, 75, 254, 211, 201, 100, 7, 58, 14, 225, 114, 243, 218,
+    166, 35, 37, 175, 2, 26, 104, 247, 7, 81, 26,
+];
+
+fn error_code(err: JsValue) -> String {
+    assert!(err.is_instance_of::<js_sys::Error>());
+    js_sys::Reflect::get(&err, &"code".into())
+        .unwrap()
+        .as_string()
+        .unwrap()
+}
+
+fn config_with(
+    revocation_pub_key: Vec<u8>,
+    device_pub_key: Vec<u8>,
+    hosting_preferences: JsValue,
+) -> Result<JsValue, JsValue> {
+    config(
+        "pj@abba.pl".into(),
+        "password".into(),
+        "registration code".into(),
+        revocation_pub_key,
+        "m/0'/2147483647'/1'".into(),
+        "bundle".into(),
+        device_pub_key,
+        hosting_preferences,
+    )
+    .map_err(JsValue::from)
+}
+
+#[wasm_bindgen_test]
+fn config_rejects_short_device_key() {
+    let err = config_with(DEVICE_PUB_KEY.to_vec(), vec![1; 31], JsValue::UNDEFINED).unwrap_err();
+    assert_eq!(error_code(err), "invalid_public_key");
+}
+
+#[wasm_bindgen_test]
+fn config_rejects_short_revocation_key() {
+    let err = config_with(vec![], DEVICE_PUB_KEY.to_vec(), JsValue::UNDEFINED).unwrap_err();
+    assert_eq!(error_code(err), "invalid_public_key");
+}
+
+#[wasm_bindgen_test]
+fn config_rejects_invalid_hosting_preferences() {
+    let err = config_with(
+        DEVICE_PUB_KEY.to_vec(),
+        DEVICE_PUB_KEY.to_vec(),
+        "cheap".into(),
+    )
+    .unwrap_err();
+    assert_eq!(error_code(err), "invalid_argument");
+
+    let preferences = js_sys::JSON::parse(r#"{"v1":{"price_compute":"-1"}}"#).unwrap();
+    let err = config_with(
+        DEVICE_PUB_KEY.to_vec(),
+        DEVICE_PUB_KEY.to_vec(),
+        preferences,
+    )
+    .unwrap_err();
+    assert_eq!(error_code(err), "invalid_argument");
+}
+
+#[wasm_bindgen_test]
+fn config_accepts_valid_input() {
+    config_with(
+        DEVICE_PUB_KEY.to_vec(),
+        DEVICE_PUB_KEY.to_vec(),
+        JsValue::NULL,
+    )
+    .unwrap();
+}
+
+#[wasm_bindgen_test]
+fn config_functions_reject_garbage() {
+    for garbage in ["", "{", "{\"v9\":{}}", "null"] {
+        let err = JsValue::from(parse_config(garbage.into()).unwrap_err());
+        assert_eq!(error_code(err), "invalid_config");
+        let err = JsValue::from(fingerprint(garbage.into()).unwrap_err());
+        assert_eq!(error_code(err), "invalid_config");
+        let err = JsValue::from(migrate_config(garbage.into()).unwrap_err());
+        assert_eq!(error_code(err), "invalid_config");
+    }
+}
+
+#[wasm_bindgen_test]
+fn config_functions_reject_short_keys() {
+    let public_key = base64::encode_config(DEVICE_PUB_KEY, base64::STANDARD_NO_PAD);
+    let seed = base64::encode_config([0; 32], base64::STANDARD_NO_PAD);
+    for (seed, public_key) in [("AAAA", public_key.as_str()), (seed.as_str(), "AAAA")] {
+        let config = format!(
+            r#"{{"v1":{{"seed":"{}","settings":{{"admin":{{"email":"pj@abba.pl","public_key":"{}"}}}}}}}}"#,
+            seed, public_key,
+        );
+        let err = JsValue::from(parse_config(config.clone()).unwrap_err());
+        assert_eq!(error_code(err), "invalid_config");
+        let err = JsValue::from(fingerprint(config).unwrap_err());
+        assert_eq!(error_code(err), "invalid_config");
+    }
+}
+
+#[wasm_bindgen_test]
+fn migrate_config_rejects_v1() {
+    let v1 = format!(
+        r#"{{"v1":{{"seed":"{}","settings":{{"admin":{{"email":"pj@abba.pl","public_key":"{}"}}}}}}}}"#,
+        base64::encode_config([0; 32], base64::STANDARD_NO_PAD),
+        base64::encode_config(DEVICE_PUB_KEY, base64::STANDARD_NO_PAD),
+    );
+    parse_config(v1.clone()).unwrap();
+    let err = JsValue::from(migrate_config(v1).unwrap_err());
+    assert_eq!(error_code(err), "unsupported");
+}
+
+#[wasm_bindgen_test]
+fn key_functions_reject_short_keys() {
+    let err = JsValue::from(holoport_ids(vec![0; 33]).unwrap_err());
+    assert_eq!(error_code(err), "invalid_public_key");
+    let err = JsValue::from(
+        admin_public_key("pj@abba.pl".into(), "password".into(), vec![]).unwrap_err(),
+    );
+    assert_eq!(error_code(err), "invalid_public_key");
+}
+
+#[wasm_bindgen_test]
+async fn verify_registration_code_rejects_bad_input() {
+    for service_url in ["", "not a url", "mailto:hbs@holo.host"] {
+        let err = verify_registration_code(service_url.into(), "".into(), "".into())
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(err.into()), "invalid_argument");
+    }
+    // nothing listens there, so the request itself fails
+    let err = verify_registration_code("http://127.0.0.1:1".into(), "".into(), "".into())
+        .await
+        .unwrap_err();
+    assert_eq!(error_code(err.into()), "service");
+}
+
+async fn config_from_kept_seed(device_number: u32) -> Result<JsValue, JsValue> {
+    config_from_master_seed(
+        device_number,
+        "pj@abba.pl".into(),
+        "password".into(),
+        "registration code".into(),
+        JsValue::UNDEFINED,
+        None,
+    )
+    .await
+    .map_err(JsValue::from)
+}
+
+#[wasm_bindgen_test]
+async fn config_from_master_seed_needs_a_kept_seed() {
+    zero_master_seed();
+    let err = config_from_kept_seed(1).await.unwrap_err();
+    assert_eq!(error_code(err), "missing_seed");
+
+    let files = generate_seed_files("seed passphrase".into()).await.unwrap();
+    for file in ["master_seed", "revocation_key"] {
+        let file = js_sys::Reflect::get(&files, &file.into()).unwrap();
+        assert!(!file.as_string().unwrap().is_empty());
+    }
+    let err = config_from_kept_seed(0).await.unwrap_err();
+    assert_eq!(error_code(err), "invalid_argument");
+    let data = config_from_kept_seed(1).await.unwrap();
+    let config = js_sys::Reflect::get(&data, &"config".into()).unwrap();
+    parse_config(config.as_string().unwrap()).unwrap();
+
+    zero_master_seed();
+    let err = config_from_kept_seed(1).await.unwrap_err();
+    assert_eq!(error_code(err), "missing_seed");
+}
@@ -0,0 +1,52 @@
+//! Prints a redacted summary of a hpos-config file, safe to paste into
+//! support tickets: device bundles, seeds and registration codes are only
+//! shown by fingerprint.
+//!
+//! `--fingerprint` prints only the stable fingerprint of the whole config,
+//! e.g. for recording in an inventory database.
+
+use anyhow::{anyhow, Result};
+use hpos_config_core::*;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+fn main() -> Result<()> {
+    #[derive(StructOpt)]
+    struct Cli {
+        #[structopt(long = "config-path")]
+        /// The path to the hpos-config file, defaults to $HPOS_CONFIG_PATH
+        /// or a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "mask-email")]
+        /// Only show the first character of the admin email
+        mask_email: bool,
+        #[structopt(long = "json")]
+        /// Print the summary as JSON
+        json: bool,
+        #[structopt(long = "fingerprint", conflicts_with = "json")]
+        /// Only print the config fingerprint
+        fingerprint: bool,
+    }
+
+    let Cli {
+        config_path,
+        mask_email,
+        json,
+        fingerprint,
+    } = Cli::from_args();
+
+    let (config, _) = load(config_path.as_deref())?;
+    if fingerprint {
+        let fingerprint = config.fingerprint().map_err(|e| anyhow!("{}", e))?;
+        println!("{}", fingerprint);
+        return Ok(());
+    }
+    let summary = config.summary(mask_email);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        print!("{}", summary);
+    }
+
+    Ok(())
+}
@@ -2,7 +2,6 @@ use anyhow::{Context, Result};
 use ed25519_dalek::*;
 use hpos_config_core::*;
 use hpos_config_seed_bundle_explorer::unlock;
-use std::fs::File;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -11,8 +10,9 @@ async fn main() -> Result<()> {
     #[derive(StructOpt)]
     struct Cli {
         #[structopt(long = "config-path")]
-        /// The path to the hpos-config file
-        config_path: PathBuf,
+        /// The path to the hpos-config file, defaults to $HPOS_CONFIG_PATH
+        /// or a well-known location
+        config_path: Option<PathBuf>,
         #[structopt(long = "password")]
         /// The password to unlock the device-bundle
         password: String,
@@ -24,11 +24,8 @@ async fn main() -> Result<()> {
         ..
     } = Cli::from_args();
 
-    let config_file = File::open(&config_path).context(format!(
-        "failed to open file {}",
-        &config_path.to_string_lossy()
-    ))?;
-    match serde_json::from_reader(config_file)? {
+    let (config, config_path) = load(config_path.as_deref())?;
+    match config {
         Config::V1 { seed, .. } => {
             let public_key = VerifyingKey::from_bytes(&seed)?;
             println!("{}", public_key::to_base36_id(&public_key));
@@ -43,7 +40,7 @@ async fn main() -> Result<()> {
                 ))?;
             println!("{}", public_key::to_base36_id(&secret.verifying_key()));
         }
-        Config::V3 { holoport_id, .. } => {
+        Config::V3 { holoport_id, .. } | Config::V4 { holoport_id, .. } => {
             println!("{}", holoport_id);
         }
     }
@@ -7,5 +7,6 @@ fn main() -> Result<()> {
         Config::V1 { .. } => Ok(()),
         Config::V2 { .. } => Ok(()),
         Config::V3 { .. } => Ok(()),
+        Config::V4 { .. } => Ok(()),
     }
 }
@@ -0,0 +1,139 @@
+//! Stand-in for the membrane proof service's registration code check, so
+//! gen-cli and Quickstart can be exercised offline. Codes are accepted if
+//! they are listed in a fixture file, together with the email they were
+//! issued to.
+
+use hpos_config_core::registration::{Role, ServiceError, VerifyRequest, VERIFY_PATH};
+use serde::*;
+use std::fs;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Registration {
+    pub email: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Fixture {
+    pub registrations: Vec<Registration>,
+}
+
+impl Fixture {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// The response the real service gives, as status and JSON body
+    fn verify(&self, request: &VerifyRequest) -> (u16, String) {
+        let registration = self
+            .registrations
+            .iter()
+            .find(|registration| registration.code == request.code);
+        let rejection = match registration {
+            _ if request.role != Role::Host => "Only host registration codes are supported",
+            None => "Unknown registration code",
+            Some(registration) if !registration.email.eq_ignore_ascii_case(&request.email) => {
+                "Registration code was issued to a different email address"
+            }
+            Some(_) => return (200, "{}".into()),
+        };
+        service_error("Invalid registration code", rejection, true)
+    }
+}
+
+fn service_error(error: &str, info: &str, is_displayed_to_user: bool) -> (u16, String) {
+    let body = ServiceError {
+        error: error.into(),
+        info: info.into(),
+        is_displayed_to_user,
+    };
+    (500, serde_json::to_string(&body).unwrap_or_default())
+}
+
+/// Server answering verify requests from `fixture` on a background thread,
+/// stopped when dropped
+pub struct MockServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Listen on `addr`, port 0 picks a free one
+    pub fn start(addr: SocketAddr, fixture: Fixture) -> io::Result<Self> {
+        let server = Arc::new(
+            Server::http(addr).map_err(|err| io::Error::new(io::ErrorKind::AddrInUse, err))?,
+        );
+        let thread = thread::spawn({
+            let server = server.clone();
+            move || {
+                for request in server.incoming_requests() {
+                    respond(&fixture, request);
+                }
+            }
+        });
+        Ok(MockServer {
+            server,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.server
+            .server_addr()
+            .to_ip()
+            .expect("mock server listens on an IP address")
+    }
+
+    /// Url to pass as membrane proof service url
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr())
+    }
+
+    /// Serve until the process is killed
+    pub fn join(mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn respond(fixture: &Fixture, mut request: Request) {
+    let (status, body) = if request.url().trim_start_matches('/') != VERIFY_PATH {
+        (404, String::new())
+    } else if *request.method() != Method::Post {
+        (405, String::new())
+    } else {
+        let mut body = String::new();
+        match io::Read::read_to_string(request.as_reader(), &mut body)
+            .map_err(|err| err.to_string())
+            .and_then(|_| serde_json::from_str(&body).map_err(|err| err.to_string()))
+        {
+            Ok(verify_request) => fixture.verify(&verify_request),
+            Err(err) => service_error("Malformed request", &err, false),
+        }
+    };
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(
+            "Content-Type: application/json"
+                .parse::<Header>()
+                .expect("valid header"),
+        );
+    // the client may have hung up already
+    let _ = request.respond(response);
+}
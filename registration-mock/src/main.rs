@@ -0,0 +1,30 @@
+//! Serves the membrane proof service's registration code check from a
+//! fixture file, e.g. for running Quickstart or gen-cli offline:
+//!
+//! hpos-config-registration-mock --fixture fixtures/registrations.json --port 8800
+
+use anyhow::Result;
+use hpos_config_registration_mock::{Fixture, MockServer};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+fn main() -> Result<()> {
+    #[derive(StructOpt)]
+    struct Cli {
+        #[structopt(long = "fixture")]
+        /// JSON file listing the valid registrations, see fixtures/registrations.json
+        fixture: PathBuf,
+        #[structopt(long = "port", default_value = "8800")]
+        /// Port to listen on, on localhost
+        port: u16,
+    }
+
+    let Cli { fixture, port } = Cli::from_args();
+
+    let fixture = Fixture::from_file(&fixture)?;
+    let server = MockServer::start(SocketAddr::from((Ipv4Addr::LOCALHOST, port)), fixture)?;
+    eprintln!("Serving registration code checks on {}", server.url());
+    server.join();
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use hpos_config_core::registration::{RegistrationClient, RegistrationError, Verification};
+use hpos_config_registration_mock::{Fixture, MockServer};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use url::Url;
+
+fn start() -> MockServer {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/registrations.json");
+    MockServer::start(
+        SocketAddr::from((Ipv4Addr::LOCALHOST, 0)),
+        Fixture::from_file(&fixture).unwrap(),
+    )
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verifies_registration_codes() {
+    let server = start();
+    let client = RegistrationClient::new(&Url::parse(&server.url()).unwrap()).unwrap();
+
+    assert_eq!(
+        client
+            .verify("pj@abba.pl", "VALID-REGISTRATION-CODE")
+            .await
+            .unwrap(),
+        Verification::Valid
+    );
+    assert_eq!(
+        client
+            .verify("PJ@abba.pl", "VALID-REGISTRATION-CODE")
+            .await
+            .unwrap(),
+        Verification::Valid
+    );
+    assert!(matches!(
+        client.verify("pj@abba.pl", "UNKNOWN").await.unwrap(),
+        Verification::Rejected(_)
+    ));
+    assert!(matches!(
+        client
+            .verify("joel@holo.host", "VALID-REGISTRATION-CODE")
+            .await
+            .unwrap(),
+        Verification::Rejected(_)
+    ));
+
+    // a base url pointing elsewhere is an error, not a rejection
+    let client = RegistrationClient::new(
+        &Url::parse(&server.url())
+            .unwrap()
+            .join("/elsewhere/")
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(matches!(
+        client.verify("pj@abba.pl", "VALID-REGISTRATION-CODE").await,
+        Err(RegistrationError::Status(404))
+    ));
+}
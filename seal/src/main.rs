@@ -0,0 +1,105 @@
+//! Encrypts a hpos-config file at rest, or opens a sealed one again. Configs
+//! are sealed with a passphrase or for a HoloPort, which opens them with its
+//! own key (e.g. exported with `hpos-config-seed-encoder --format pem`).
+
+use anyhow::{Context, Result};
+use ed25519_dalek::{pkcs8::DecodePrivateKey, SigningKey};
+use hpos_config_core::*;
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(about = "Seal and unseal hpos-config files")]
+enum Cli {
+    /// Encrypt a config file
+    Seal {
+        #[structopt(long = "config-path")]
+        /// The path to the hpos-config file, defaults to $HPOS_CONFIG_PATH
+        /// or a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "output")]
+        /// Where to write the sealed config, defaults to replacing the input
+        output: Option<PathBuf>,
+        #[structopt(long = "passphrase", required_unless = "holoport_id")]
+        /// Seal with this passphrase
+        passphrase: Option<String>,
+        #[structopt(long = "holoport-id", conflicts_with = "passphrase")]
+        /// Seal for the HoloPort with this base36 id
+        holoport_id: Option<String>,
+    },
+    /// Decrypt a sealed config file
+    Unseal {
+        #[structopt(long = "config-path")]
+        /// The path to the sealed hpos-config file, defaults to
+        /// $HPOS_CONFIG_PATH or a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "output")]
+        /// Where to write the plain config, defaults to replacing the input
+        output: Option<PathBuf>,
+        #[structopt(long = "passphrase", required_unless = "device_key")]
+        /// The passphrase the config was sealed with
+        passphrase: Option<String>,
+        #[structopt(long = "device-key", conflicts_with = "passphrase")]
+        /// Path to the HoloPort's PKCS#8 PEM private key
+        device_key: Option<PathBuf>,
+    },
+}
+
+fn main() -> Result<()> {
+    match Cli::from_args() {
+        Cli::Seal {
+            config_path,
+            output,
+            passphrase,
+            holoport_id,
+        } => {
+            let (config, config_path) = load(config_path.as_deref())?;
+            let output = output.unwrap_or(config_path);
+            match (passphrase, holoport_id) {
+                (Some(passphrase), _) => {
+                    save_sealed(&config, &output, &SealKey::Passphrase(&passphrase))
+                }
+                (None, Some(holoport_id)) => {
+                    let device_key = public_key::from_base36_id(&holoport_id)
+                        .map_err(|e| anyhow::anyhow!("invalid holoport id: {}", e))?;
+                    save_sealed(&config, &output, &SealKey::Device(&device_key))
+                }
+                (None, None) => unreachable!("enforced by structopt"),
+            }
+            .context(format!(
+                "failed to write file {}",
+                &output.to_string_lossy()
+            ))?;
+        }
+        Cli::Unseal {
+            config_path,
+            output,
+            passphrase,
+            device_key,
+        } => {
+            let (config, config_path) = match (passphrase, device_key) {
+                (Some(passphrase), _) => {
+                    load_sealed(config_path.as_deref(), &UnsealKey::Passphrase(&passphrase))?
+                }
+                (None, Some(device_key)) => {
+                    let pem = fs::read_to_string(&device_key).context(format!(
+                        "failed to open file {}",
+                        &device_key.to_string_lossy()
+                    ))?;
+                    let device_key = SigningKey::from_pkcs8_pem(&pem)
+                        .context("device key is not an ed25519 PKCS#8 PEM key")?;
+                    load_sealed(config_path.as_deref(), &UnsealKey::Device(&device_key))?
+                }
+                (None, None) => unreachable!("enforced by structopt"),
+            };
+            let output = output.unwrap_or(config_path);
+            save(&config, &output).context(format!(
+                "failed to write file {}",
+                &output.to_string_lossy()
+            ))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Export the holoport signing key in formats understood by standard tooling,
+//! so the HoloPort identity can be used with `ssh-keygen -Y`, openssl or JOSE libraries.
+
+use crate::{encrypt_key, encrypt_key_with_passphrase, SeedExplorerError, SeedExplorerResult};
+use ed25519_dalek::SigningKey;
+use hpos_config_core::public_key;
+use pkcs8::{rand_core::OsRng, EncodePrivateKey, EncodePublicKey, LineEnding};
+use serde_json::json;
+use ssh_key::private::{Ed25519Keypair, KeypairData};
+use std::str::FromStr;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Output formats supported by `export_key`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// lair (<v0.0.6) keypair blob, see `encrypt_key`
+    Lair,
+    /// PKCS#8 private key and SPKI public key, PEM encoded
+    Pem,
+    /// OpenSSH private key and `authorized_keys` style public key
+    OpenSsh,
+    /// JSON Web Key of type OKP (RFC 8037)
+    Jwk,
+}
+
+impl FromStr for KeyFormat {
+    type Err = SeedExplorerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lair" => Ok(KeyFormat::Lair),
+            "pem" => Ok(KeyFormat::Pem),
+            "openssh" => Ok(KeyFormat::OpenSsh),
+            "jwk" => Ok(KeyFormat::Jwk),
+            _ => Err(SeedExplorerError::Generic(format!(
+                "Unknown key format {}, expected one of lair, pem, openssh, jwk",
+                s
+            ))),
+        }
+    }
+}
+
+/// Private and public halves of an exported key, zeroized on drop
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct ExportedKey {
+    pub private_key: String,
+    /// `None` for formats that embed the public key in the private key
+    pub public_key: Option<String>,
+}
+
+/// Export the keypair in the given format.
+///
+/// With a passphrase the private key is encrypted: PBES2 for PEM, bcrypt-pbkdf
+/// for OpenSSH and `encrypt_key_with_passphrase` for lair. JWK has no
+/// passphrase protection and is refused.
+pub async fn export_key(
+    secret: &SigningKey,
+    format: KeyFormat,
+    passphrase: Option<String>,
+) -> SeedExplorerResult<ExportedKey> {
+    let verifying_key = secret.verifying_key();
+    match format {
+        KeyFormat::Lair => Ok(ExportedKey {
+            private_key: match passphrase {
+                None => encrypt_key(secret, &verifying_key),
+                Some(passphrase) => {
+                    encrypt_key_with_passphrase(secret, &verifying_key, passphrase).await?
+                }
+            },
+            public_key: None,
+        }),
+        KeyFormat::Pem => {
+            let private_key = match passphrase {
+                None => secret.to_pkcs8_pem(LineEnding::LF)?,
+                Some(passphrase) => {
+                    secret.to_pkcs8_encrypted_pem(OsRng, passphrase, LineEnding::LF)?
+                }
+            };
+            Ok(ExportedKey {
+                private_key: private_key.to_string(),
+                public_key: Some(verifying_key.to_public_key_pem(LineEnding::LF)?),
+            })
+        }
+        KeyFormat::OpenSsh => {
+            let mut private_key = ssh_key::PrivateKey::new(
+                KeypairData::from(Ed25519Keypair::from(secret)),
+                public_key::to_base36_id(&verifying_key),
+            )?;
+            if let Some(passphrase) = passphrase {
+                private_key = private_key.encrypt(&mut OsRng, passphrase)?;
+            }
+            Ok(ExportedKey {
+                private_key: private_key.to_openssh(ssh_key::LineEnding::LF)?.to_string(),
+                public_key: Some(private_key.public_key().to_openssh()?),
+            })
+        }
+        KeyFormat::Jwk => {
+            if passphrase.is_some() {
+                return Err(SeedExplorerError::Generic(
+                    "JWK export can not be passphrase protected".into(),
+                ));
+            }
+            let x = base64::encode_config(verifying_key.to_bytes(), base64::URL_SAFE_NO_PAD);
+            let d = Zeroizing::new(base64::encode_config(
+                secret.to_bytes(),
+                base64::URL_SAFE_NO_PAD,
+            ));
+            let kid = public_key::to_base36_id(&verifying_key);
+            Ok(ExportedKey {
+                private_key: json!({ "kty": "OKP", "crv": "Ed25519", "x": x, "d": *d, "kid": kid })
+                    .to_string(),
+                public_key: Some(
+                    json!({ "kty": "OKP", "crv": "Ed25519", "x": x, "kid": kid }).to_string(),
+                ),
+            })
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Rebuild a config around an existing keypair, e.g. one recovered with
+//! `decoded_to_ed25519_keypair` on a HoloPort where only the lair key survived.
+
+use crate::{lock, SeedExplorerError, SeedExplorerResult};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use hpos_config_core::{
+    admin_keypair_from,
+    config::{Admin, Seed, Settings},
+    Config,
+};
+
+/// V1 config carrying the secret key as its seed
+pub fn v1_config_from_keypair(
+    secret: &SigningKey,
+    email: String,
+    password: String,
+) -> SeedExplorerResult<Config> {
+    let admin_keypair = admin_keypair_from(secret.verifying_key(), &email, &password)
+        .map_err(|err| SeedExplorerError::Generic(err.to_string()))?;
+    Ok(Config::V1 {
+        seed: Seed::from(secret.to_bytes()),
+        settings: Settings {
+            admin: Admin {
+                email,
+                public_key: admin_keypair.verifying_key(),
+            },
+            hosting_preferences: None,
+            network: None,
+        },
+    })
+}
+
+/// V3 config with the secret key locked into a new device bundle.
+///
+/// Without a `revocation_pub_key` the holoport key itself is used, as `gen-cli` does.
+pub async fn v3_config_from_keypair(
+    secret: &SigningKey,
+    device_bundle_passphrase: String,
+    email: String,
+    password: String,
+    registration_code: String,
+    derivation_path: String,
+    revocation_pub_key: Option<VerifyingKey>,
+) -> SeedExplorerResult<Config> {
+    let device_bundle = lock(secret, device_bundle_passphrase).await?;
+    let (config, _) = Config::new(
+        email,
+        password,
+        registration_code,
+        revocation_pub_key.unwrap_or_else(|| secret.verifying_key()),
+        derivation_path,
+        device_bundle,
+        secret.verifying_key(),
+    )
+    .map_err(|err| SeedExplorerError::Generic(err.to_string()))?;
+    Ok(config)
+}
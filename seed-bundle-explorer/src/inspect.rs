@@ -0,0 +1,136 @@
+//! Decode a locked seed bundle without unlocking it, to tell which ciphers,
+//! pwhash limits and Quickstart version produced it.
+
+use crate::{SeedExplorerError, SeedExplorerResult};
+use rmpv::Value;
+use serde::Serialize;
+
+/// Everything readable from a locked seed bundle without its passphrase
+#[derive(Debug, Serialize)]
+pub struct BundleInfo {
+    /// Format tag of the bundle, `hcsb0` for current bundles
+    pub format: String,
+    pub ciphers: Vec<CipherInfo>,
+    /// msgpack app data, e.g. `device_number` and `generate_by` for Quickstart bundles
+    pub app_data: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CipherInfo {
+    PwHash {
+        mem_limit: u64,
+        ops_limit: u64,
+        /// Name of the matching hc_seed_bundle preset, if any
+        limits: Option<String>,
+    },
+    SecurityQuestions {
+        mem_limit: u64,
+        ops_limit: u64,
+        limits: Option<String>,
+        questions: Vec<String>,
+    },
+    Unsupported {
+        cipher_type: String,
+    },
+}
+
+/// (name, ops limit, mem limit) of the libsodium argon2id presets
+const PWHASH_LIMITS: &[(&str, u64, u64)] = &[
+    ("minimum", 1, 8192),
+    ("interactive", 2, 67108864),
+    ("moderate", 3, 268435456),
+    ("sensitive", 4, 1073741824),
+];
+
+/// decode the base64 device bundle into its cipher list and app data
+pub fn inspect_bundle(device_bundle: &String) -> SeedExplorerResult<BundleInfo> {
+    let bytes = base64::decode_config(device_bundle, base64::URL_SAFE_NO_PAD)?;
+    let bundle = rmpv::decode::read_value(&mut &bytes[..])
+        .map_err(|err| SeedExplorerError::Generic(err.to_string()))?;
+
+    let (format, ciphers, app_data) = match bundle.as_array().map(Vec::as_slice) {
+        Some([format, ciphers, app_data]) => (format, ciphers, app_data),
+        _ => return Err(malformed("expected a 3 element array")),
+    };
+    let format = format
+        .as_str()
+        .ok_or_else(|| malformed("format tag is not a string"))?
+        .to_string();
+    let ciphers = ciphers
+        .as_array()
+        .ok_or_else(|| malformed("cipher list is not an array"))?
+        .iter()
+        .map(inspect_cipher)
+        .collect::<SeedExplorerResult<Vec<_>>>()?;
+    let app_data = match app_data.as_slice() {
+        Some(bytes) if !bytes.is_empty() => rmp_serde::from_slice(bytes)
+            .unwrap_or_else(|_| serde_json::Value::String(base64::encode(bytes))),
+        _ => serde_json::Value::Null,
+    };
+
+    Ok(BundleInfo {
+        format,
+        ciphers,
+        app_data,
+    })
+}
+
+fn inspect_cipher(cipher: &Value) -> SeedExplorerResult<CipherInfo> {
+    let fields = cipher
+        .as_array()
+        .ok_or_else(|| malformed("cipher is not an array"))?;
+    let cipher_type = fields
+        .first()
+        .and_then(Value::as_str)
+        .ok_or_else(|| malformed("cipher type is not a string"))?;
+    let limit = |index: usize| {
+        fields
+            .get(index)
+            .and_then(Value::as_u64)
+            .ok_or_else(|| malformed("pwhash limit is not an integer"))
+    };
+
+    match cipher_type {
+        // ["pw", salt, mem_limit, ops_limit, header, cipher]
+        "pw" => {
+            let (mem_limit, ops_limit) = (limit(2)?, limit(3)?);
+            Ok(CipherInfo::PwHash {
+                mem_limit,
+                ops_limit,
+                limits: limits_name(ops_limit, mem_limit),
+            })
+        }
+        // ["qa", salt, mem_limit, ops_limit, question_1, question_2, question_3, header, cipher]
+        "qa" => {
+            let (mem_limit, ops_limit) = (limit(2)?, limit(3)?);
+            let questions = fields
+                .iter()
+                .skip(4)
+                .take(3)
+                .map(|question| question.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| malformed("security question is not a string"))?;
+            Ok(CipherInfo::SecurityQuestions {
+                mem_limit,
+                ops_limit,
+                limits: limits_name(ops_limit, mem_limit),
+                questions,
+            })
+        }
+        other => Ok(CipherInfo::Unsupported {
+            cipher_type: other.to_string(),
+        }),
+    }
+}
+
+fn limits_name(ops_limit: u64, mem_limit: u64) -> Option<String> {
+    PWHASH_LIMITS
+        .iter()
+        .find(|(_, ops, mem)| *ops == ops_limit && *mem == mem_limit)
+        .map(|(name, _, _)| name.to_string())
+}
+
+fn malformed(reason: &str) -> SeedExplorerError {
+    SeedExplorerError::Generic(format!("Malformed seed bundle: {}", reason))
+}
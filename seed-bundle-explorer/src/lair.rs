@@ -0,0 +1,216 @@
+//! Export the device seed for the lair-keystore version running on the HoloPort.
+//!
+//! lair (<v0.0.6) loads a keypair blob through `--load_ed25519_keypair_from_seed`,
+//! see `encrypt_key`. lair (>=v0.1) keeps its keys in an encrypted store under
+//! the lair root and only takes seeds as passphrase locked seed bundles through
+//! `lair-keystore import-seed <tag> <seed bundle>`. The two are not interchangeable,
+//! so the version is always given explicitly rather than guessed.
+
+use crate::{encrypt_key, lock, SeedExplorerError, SeedExplorerResult};
+use ed25519_dalek::SigningKey;
+use serde::Deserialize;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Written by `lair-keystore init` into every lair (>=v0.1) root
+pub const LAIR_CONFIG_FILE: &str = "lair-keystore-config.yaml";
+
+/// Which lair-keystore import an export is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LairVersion {
+    /// lair (<v0.0.6), `--load_ed25519_keypair_from_seed`
+    Legacy,
+    /// lair (>=v0.1), `lair-keystore import-seed`
+    SeedBundle,
+}
+
+impl FromStr for LairVersion {
+    type Err = SeedExplorerError;
+
+    /// Parse a lair-keystore release such as `0.4.5` or `v0.0.3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SeedExplorerError::Generic(format!("Invalid lair-keystore version {}", s));
+        let mut parts = s.strip_prefix('v').unwrap_or(s).splitn(3, '.');
+        let mut next = |required: bool| match parts.next() {
+            Some(part) => part.parse::<u64>().map_err(|_| invalid()),
+            None if required => Err(invalid()),
+            None => Ok(0),
+        };
+        let version = (next(true)?, next(true)?, next(false)?);
+        match version {
+            (0, 0, patch) if patch < 6 => Ok(LairVersion::Legacy),
+            (0, 0, _) => Err(SeedExplorerError::Generic(format!(
+                "lair-keystore {} is not supported, expected <0.0.6 or >=0.1",
+                s
+            ))),
+            _ => Ok(LairVersion::SeedBundle),
+        }
+    }
+}
+
+impl fmt::Display for LairVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            LairVersion::Legacy => "lair-keystore (<v0.0.6)",
+            LairVersion::SeedBundle => "lair-keystore (>=v0.1)",
+        })
+    }
+}
+
+/// What the selected lair-keystore version imports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LairExport {
+    /// Keypair blob for `--load_ed25519_keypair_from_seed`
+    Keypair(String),
+    /// Base64 seed bundle to import under `tag` with `lair-keystore import-seed`
+    SeedBundle { tag: String, seed_bundle: String },
+}
+
+impl LairExport {
+    /// Write the export to a new file at `path` that only its owner can read.
+    /// Fails if `path` exists, rather than widening who can read an existing file.
+    pub fn write(&self, path: &Path) -> SeedExplorerResult<()> {
+        let contents = match self {
+            LairExport::Keypair(blob) => blob,
+            LairExport::SeedBundle { seed_bundle, .. } => seed_bundle,
+        };
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", contents))
+            .map_err(|err| {
+                SeedExplorerError::Generic(format!(
+                    "failed to write file {}: {}",
+                    path.to_string_lossy(),
+                    err
+                ))
+            })
+    }
+
+    /// Shell command importing a seed bundle into the lair at `lair_root`,
+    /// reading it from `seed_bundle_file` as written by `write`. With
+    /// `--piped` lair reads its own passphrase and then the seed bundle
+    /// passphrase from stdin. `None` for keypair blobs, which lair (<v0.0.6)
+    /// only loads on start.
+    ///
+    /// lair only takes the seed bundle as an argument, so it is read from the
+    /// file when the command runs and never ends up in the printed command or
+    /// the shell history.
+    pub fn import_command(&self, lair_root: &Path, seed_bundle_file: &Path) -> Option<String> {
+        match self {
+            LairExport::Keypair(_) => None,
+            LairExport::SeedBundle { tag, .. } => Some(format!(
+                "lair-keystore --lair-root {} import-seed --piped {} \"$(cat {})\"",
+                shell_string(&lair_root.to_string_lossy()),
+                shell_string(tag),
+                shell_string(&seed_bundle_file.to_string_lossy())
+            )),
+        }
+    }
+}
+
+/// The parts of `lair-keystore-config.yaml` an import relies on
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LairConfig {
+    /// `unix://` socket or `named_pipe:` the import connects to
+    pub connection_url: String,
+    /// Encrypted store the seed ends up in, always absolute
+    pub store_file: PathBuf,
+}
+
+impl LairConfig {
+    fn check(&self) -> Result<(), String> {
+        if !["unix://", "named_pipe:"]
+            .iter()
+            .any(|scheme| self.connection_url.starts_with(scheme))
+        {
+            return Err("connectionUrl is neither a unix socket nor a named pipe".to_string());
+        }
+        if !self.store_file.is_absolute() {
+            return Err(format!(
+                "storeFile {} is not an absolute path",
+                self.store_file.to_string_lossy()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Export the device seed for `version`.
+///
+/// lair (>=v0.1) refuses unencrypted seeds, so `passphrase` is required for
+/// it and locks the exported seed bundle. lair (<v0.0.6) can only load the
+/// plain keypair blob of `encrypt_key`, so `passphrase` is rejected for it and
+/// `tag` is unused.
+pub async fn export_for_lair(
+    secret: &SigningKey,
+    version: LairVersion,
+    tag: String,
+    passphrase: Option<String>,
+) -> SeedExplorerResult<LairExport> {
+    let verifying_key = secret.verifying_key();
+    match version {
+        LairVersion::Legacy if passphrase.is_some() => Err(SeedExplorerError::Generic(format!(
+            "{} can't load passphrase protected keypairs",
+            version
+        ))),
+        LairVersion::Legacy => Ok(LairExport::Keypair(encrypt_key(secret, &verifying_key))),
+        LairVersion::SeedBundle => {
+            let passphrase = passphrase.ok_or(SeedExplorerError::PasswordRequired)?;
+            Ok(LairExport::SeedBundle {
+                tag,
+                seed_bundle: lock(secret, passphrase).await?,
+            })
+        }
+    }
+}
+
+/// Check that `lair_root` holds a store of the selected `version`, returning
+/// its config for lair (>=v0.1) once the connection url and store file look sane
+pub fn check_lair_root(
+    lair_root: &Path,
+    version: LairVersion,
+) -> SeedExplorerResult<Option<LairConfig>> {
+    let config_path = lair_root.join(LAIR_CONFIG_FILE);
+    match version {
+        LairVersion::Legacy if config_path.exists() => Err(SeedExplorerError::Generic(format!(
+            "{} belongs to {}, not {}",
+            lair_root.to_string_lossy(),
+            LairVersion::SeedBundle,
+            version
+        ))),
+        LairVersion::Legacy => Ok(None),
+        LairVersion::SeedBundle => {
+            let config = std::fs::read_to_string(&config_path).map_err(|err| {
+                SeedExplorerError::Generic(format!(
+                    "Unable to read {}, run `lair-keystore init` first: {}",
+                    config_path.to_string_lossy(),
+                    err
+                ))
+            })?;
+            let invalid = |err: String| {
+                SeedExplorerError::Generic(format!(
+                    "{} is not a {} config: {}",
+                    config_path.to_string_lossy(),
+                    version,
+                    err
+                ))
+            };
+            let config: LairConfig =
+                serde_yaml::from_str(&config).map_err(|err| invalid(err.to_string()))?;
+            config.check().map_err(invalid)?;
+            Ok(Some(config))
+        }
+    }
+}
+
+fn shell_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
@@ -1,7 +1,17 @@
+pub mod export;
+pub mod import;
+pub mod inspect;
+pub mod lair;
+pub mod mnemonic;
+pub mod shamir;
+
 use ed25519_dalek::{ed25519, SigningKey, VerifyingKey};
 use hc_seed_bundle::*;
 use hpos_config_core::Config;
 use log::debug;
+use serde::Serialize;
+use sodoken::{hash::argon2id, secretstream::xchacha20poly1305};
+use zeroize::Zeroizing;
 
 /// get pub key for the device bundle in the config
 pub async fn holoport_public_key(
@@ -22,7 +32,7 @@ pub async fn holoport_public_key(
             let secret = unlock(device_bundle, passphrase).await?;
             Ok(secret.verifying_key())
         }
-        Config::V3 { holoport_id, .. } => {
+        Config::V3 { holoport_id, .. } | Config::V4 { holoport_id, .. } => {
             let value = match (base36::decode(&holoport_id)
                 .map_err(|err| SeedExplorerError::Generic(err.to_string()))?)[0..32]
                 .try_into()
@@ -46,7 +56,9 @@ pub async fn holoport_key(
 ) -> SeedExplorerResult<SigningKey> {
     match config {
         Config::V1 { seed, .. } => Ok(SigningKey::from_bytes(seed)),
-        Config::V2 { device_bundle, .. } | Config::V3 { device_bundle, .. } => {
+        Config::V2 { device_bundle, .. }
+        | Config::V3 { device_bundle, .. }
+        | Config::V4 { device_bundle, .. } => {
             /*
                 decode base64 string to locked device bundle
                 password is pass for now
@@ -58,54 +70,210 @@ pub async fn holoport_key(
 }
 
 /// encode the ed25519 keypair making it compatible with lair (<v0.0.6)
+///
+/// When `export_passphrase` is `None` the keypair is written in plaintext,
+/// otherwise it is encrypted with `encrypt_key_with_passphrase`
 pub async fn encoded_ed25519_keypair(
     config: &Config,
     passphrase: Option<String>,
+    export_passphrase: Option<String>,
 ) -> SeedExplorerResult<String> {
-    match config {
-        Config::V1 { seed, .. } => {
-            let secret_key = SigningKey::from_bytes(seed);
-            Ok(encrypt_key(&secret_key, &VerifyingKey::from(&secret_key)))
-        }
-        Config::V2 { device_bundle, .. } | Config::V3 { device_bundle, .. } => {
-            /*
-                decode base64 string to locked device bundle
-                password is pass for now
-                unlock it and get the signPubKey
-                Pass the Seed and VerifyingKey into `encrypt_key(seed, pubKey)`
-            */
-            let secret = unlock(device_bundle, passphrase).await?;
-            Ok(encrypt_key(&secret, &secret.verifying_key()))
+    let secret = holoport_key(config, passphrase).await?;
+    match export_passphrase {
+        None => Ok(encrypt_key(&secret, &secret.verifying_key())),
+        Some(export_passphrase) => {
+            encrypt_key_with_passphrase(&secret, &secret.verifying_key(), export_passphrase).await
         }
     }
 }
 
-/// decode the ed25519 keypair making it compatible with lair (<v0.0.6)
-pub fn decoded_to_ed25519_keypair(blob: &String) -> SeedExplorerResult<SigningKey> {
-    let decoded_key = base64::decode(blob)?;
+/// decode the ed25519 keypair produced by `encrypt_key` or `encrypt_key_with_passphrase`
+///
+/// A passphrase is only needed for encrypted blobs
+pub async fn decoded_to_ed25519_keypair(
+    blob: &String,
+    passphrase: Option<String>,
+) -> SeedExplorerResult<SigningKey> {
+    let decoded_key = Zeroizing::new(base64::decode(blob)?);
 
-    let decoded_key_bytes: [u8; 32] = match decoded_key[64..].try_into() {
-        Ok(b) => b,
-        Err(_) => {
-            return Err(SeedExplorerError::Generic(
-                "Unable to extract private key starting at position 64".into(),
-            ))
-        }
-    };
+    if decoded_key.first() == Some(&ENCRYPTED_KEY_VERSION) {
+        let passphrase = passphrase.ok_or(SeedExplorerError::PasswordRequired)?;
+        return decrypt_key(&decoded_key, passphrase).await;
+    }
+
+    if decoded_key.len() != LEGACY_PREFIX_LEN + KEYPAIR_LEN {
+        return Err(SeedExplorerError::Generic(format!(
+            "Keypair blob is {} bytes long, expected {}",
+            decoded_key.len(),
+            LEGACY_PREFIX_LEN + KEYPAIR_LEN
+        )));
+    }
+    if decoded_key[..LEGACY_PREFIX_LEN].iter().any(|b| *b != 0) {
+        return Err(SeedExplorerError::Generic(
+            "Keypair blob does not start with the 32 byte zero prefix".into(),
+        ));
+    }
+
+    keypair_from_bytes(&decoded_key[LEGACY_PREFIX_LEN..])
+}
 
-    Ok(SigningKey::from_bytes(&decoded_key_bytes))
+/// parse `public key || secret key`, checking that both halves belong together
+fn keypair_from_bytes(keypair: &[u8]) -> SeedExplorerResult<SigningKey> {
+    if keypair.len() != KEYPAIR_LEN {
+        return Err(SeedExplorerError::Generic(
+            "Keypair is not 64 bytes long".into(),
+        ));
+    }
+    let (public_key_bytes, secret_key_bytes) = keypair.split_at(32);
+    let secret_key = SigningKey::try_from(secret_key_bytes)?;
+    if secret_key.verifying_key().as_bytes()[..] != *public_key_bytes {
+        return Err(SeedExplorerError::PublicKeyMismatch);
+    }
+    Ok(secret_key)
 }
 
-// todo: we need to update this for production
-/// For now lair does not take in any encrypted bytes so we pass back an empty encrypted byte string
+/// Legacy plaintext export: lair (<v0.0.6) does not take in any encrypted bytes
+/// so we pass back an empty encrypted byte string ahead of the public and secret key.
+/// Only use this where the output never leaves the HoloPort. Newer lair versions
+/// import seed bundles instead, see `lair::export_for_lair`.
 pub fn encrypt_key(seed: &SigningKey, public_key: &VerifyingKey) -> String {
-    let mut encrypted_key = vec![
-        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        0, 0,
-    ];
+    let mut encrypted_key = Zeroizing::new(vec![0; LEGACY_PREFIX_LEN]);
     encrypted_key.extend(&public_key.to_bytes());
     encrypted_key.extend(seed.to_bytes());
-    base64::encode(&encrypted_key)
+    base64::encode(&*encrypted_key)
+}
+
+/// Version byte of passphrase encrypted keypair exports. It can never be confused
+/// with the all-zero prefix written by `encrypt_key`.
+const ENCRYPTED_KEY_VERSION: u8 = 1;
+const LEGACY_PREFIX_LEN: usize = 32;
+const KEYPAIR_LEN: usize = 64;
+const SALT_LEN: usize = argon2id::SALTBYTES;
+const HEADER_LEN: usize = xchacha20poly1305::HEADERBYTES;
+const CIPHER_LEN: usize = KEYPAIR_LEN + xchacha20poly1305::ABYTES;
+
+/// Encrypt the keypair with a passphrase.
+///
+/// The passphrase is stretched with Argon2id (interactive limits) into an
+/// XChaCha20-Poly1305 secretstream key, the same construction seed bundles use.
+/// The blob is `version || salt || header || cipher(public key || secret key)`, base64 encoded.
+pub async fn encrypt_key_with_passphrase(
+    seed: &SigningKey,
+    public_key: &VerifyingKey,
+    passphrase: String,
+) -> SeedExplorerResult<String> {
+    let salt = sodoken::BufWriteSized::<SALT_LEN>::new_no_lock();
+    sodoken::random::bytes_buf(salt.clone()).await?;
+    let salt = salt.to_read_sized();
+    let key = export_key_from_passphrase(passphrase, salt.clone()).await?;
+
+    let mut keypair = Zeroizing::new(public_key.to_bytes().to_vec());
+    keypair.extend(seed.to_bytes());
+
+    let mut enc = xchacha20poly1305::SecretStreamEncrypt::new(key)?;
+    let cipher = sodoken::BufExtend::new_no_lock(CIPHER_LEN);
+    enc.push_final(
+        secure_buf(&keypair)?,
+        <Option<sodoken::BufRead>>::None,
+        cipher.clone(),
+    )
+    .await?;
+
+    let mut encrypted_key = vec![ENCRYPTED_KEY_VERSION];
+    encrypted_key.extend(&*salt.read_lock());
+    encrypted_key.extend(&*enc.get_header().read_lock());
+    encrypted_key.extend(&*cipher.to_read().read_lock());
+    Ok(base64::encode(&encrypted_key))
+}
+
+/// reverse of `encrypt_key_with_passphrase`, takes the base64 decoded blob
+async fn decrypt_key(encrypted_key: &[u8], passphrase: String) -> SeedExplorerResult<SigningKey> {
+    if encrypted_key.len() != 1 + SALT_LEN + HEADER_LEN + CIPHER_LEN {
+        return Err(SeedExplorerError::Generic(
+            "Encrypted keypair has an unexpected length".into(),
+        ));
+    }
+    let (salt, rest) = encrypted_key[1..].split_at(SALT_LEN);
+    let (header, cipher) = rest.split_at(HEADER_LEN);
+
+    let salt: [u8; SALT_LEN] = salt.try_into().expect("split at SALT_LEN");
+    let header: [u8; HEADER_LEN] = header.try_into().expect("split at HEADER_LEN");
+    let key = export_key_from_passphrase(passphrase, sodoken::BufReadSized::from(salt)).await?;
+
+    let mut dec =
+        xchacha20poly1305::SecretStreamDecrypt::new(key, sodoken::BufReadSized::from(header))?;
+    let keypair = sodoken::BufExtend::new_no_lock(KEYPAIR_LEN);
+    dec.pull(
+        sodoken::BufRead::from(cipher.to_vec()),
+        <Option<sodoken::BufRead>>::None,
+        keypair.clone(),
+    )
+    .await
+    .map_err(|_| {
+        SeedExplorerError::Generic("Unable to decrypt keypair, wrong passphrase?".into())
+    })?;
+
+    let keypair = keypair.to_read();
+    let keypair = keypair.read_lock();
+    keypair_from_bytes(&keypair)
+}
+
+async fn export_key_from_passphrase(
+    passphrase: String,
+    salt: sodoken::BufReadSized<SALT_LEN>,
+) -> SeedExplorerResult<sodoken::BufReadSized<{ xchacha20poly1305::KEYBYTES }>> {
+    let key = sodoken::BufWriteSized::new_mem_locked()?;
+    argon2id::hash(
+        key.clone(),
+        passphrase_buf(passphrase)?,
+        salt,
+        argon2id::OPSLIMIT_INTERACTIVE,
+        argon2id::MEMLIMIT_INTERACTIVE,
+    )
+    .await?;
+    Ok(key.to_read_sized())
+}
+
+/// lock the seed into a new device bundle with a PwHash cipher, encoded the way `unlock` expects.
+/// Uses the `minimum` pwhash limits like Quickstart, so HPOS unlocks it within seconds
+pub async fn lock(seed: &SigningKey, passphrase: String) -> SeedExplorerResult<String> {
+    let bundle =
+        UnlockedSeedBundle::new_from_seed(sodoken::BufReadSized::from(seed.to_bytes())).await?;
+    lock_bundle(&bundle, passphrase).await
+}
+
+/// Size of the seed inside every seed bundle
+pub const SEED_LEN: usize = 32;
+
+/// Bundle app data as written by Quickstart
+#[derive(Serialize)]
+struct AppData {
+    #[serde(rename = "bundleType")]
+    bundle_type: &'static str,
+}
+
+/// Lock the seed into a new master seed bundle, with the `minimum` pwhash
+/// limits like the `master-seed` file Quickstart saves
+pub async fn lock_master_seed(
+    seed: &[u8; SEED_LEN],
+    passphrase: String,
+) -> SeedExplorerResult<String> {
+    let mut bundle = UnlockedSeedBundle::new_from_seed(sodoken::BufReadSized::from(*seed)).await?;
+    bundle.set_app_data(&AppData {
+        bundle_type: "master",
+    })?;
+    lock_bundle(&bundle, passphrase).await
+}
+
+async fn lock_bundle(
+    bundle: &UnlockedSeedBundle,
+    passphrase: String,
+) -> SeedExplorerResult<String> {
+    let passphrase = passphrase_buf(passphrase)?;
+    let cipher = PwHashLimits::Minimum
+        .with_exec(|| bundle.lock().add_pwhash_cipher(passphrase).lock())
+        .await?;
+    Ok(base64::encode_config(&cipher, base64::URL_SAFE_NO_PAD))
 }
 
 /// unlock seed_bundles to access the pub-key and seed
@@ -113,36 +281,65 @@ pub async fn unlock(
     device_bundle: &String,
     passphrase: Option<String>,
 ) -> SeedExplorerResult<SigningKey> {
+    let seed = unlock_bundle(device_bundle, passphrase).await?;
+
+    debug!("Casting seed to 32-byte slice.");
+    let seed_bytes: Zeroizing<[u8; 32]> = match (&*seed.get_seed().read_lock())[0..32].try_into() {
+        Ok(b) => Zeroizing::new(b),
+        Err(_) => {
+            debug!("Seed not 32 bytes: {:?}", &seed.get_seed());
+            return Err(SeedExplorerError::Generic(
+                "Seed buffer is not 32 bytes long".into(),
+            ));
+        }
+    };
+
+    Ok(SigningKey::from_bytes(&seed_bytes))
+}
+
+/// re-encrypt the device bundle under a new passphrase with a PwHash cipher,
+/// keeping its seed and app data
+pub async fn relock(
+    device_bundle: &String,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> SeedExplorerResult<String> {
+    let seed = unlock_bundle(device_bundle, Some(old_passphrase)).await?;
+    debug!("Locking seed with new passphrase.");
+    lock_bundle(&seed, new_passphrase).await
+}
+
+async fn unlock_bundle(
+    device_bundle: &String,
+    passphrase: Option<String>,
+) -> SeedExplorerResult<UnlockedSeedBundle> {
     debug!("Base64 decoding device bundle.");
     let cipher = base64::decode_config(device_bundle, base64::URL_SAFE_NO_PAD)?;
     debug!("Matching device bundle cipher.");
     match UnlockedSeedBundle::from_locked(&cipher).await?.remove(0) {
         LockedSeedCipher::PwHash(cipher) => {
-            let passphrase = passphrase
-                .as_ref()
-                .ok_or(SeedExplorerError::PasswordRequired)?;
+            let passphrase = passphrase.ok_or(SeedExplorerError::PasswordRequired)?;
             debug!("PwHash cipher used and password present.");
-            let passphrase = sodoken::BufRead::from(passphrase.as_bytes().to_vec());
+            let passphrase = passphrase_buf(passphrase)?;
             debug!("Unlocking seed with passphrase.");
-            let seed = cipher.unlock(passphrase).await?;
-
-            debug!("Casting seed to 32-byte slice.");
-            let seed_bytes: [u8; 32] = match (&*seed.get_seed().read_lock())[0..32].try_into() {
-                Ok(b) => b,
-                Err(_) => {
-                    debug!("Seed not 32 bytes: {:?}", &seed.get_seed());
-                    return Err(SeedExplorerError::Generic(
-                        "Seed buffer is not 32 bytes long".into(),
-                    ));
-                }
-            };
-
-            Ok(SigningKey::from_bytes(&seed_bytes))
+            Ok(cipher.unlock(passphrase).await?)
         }
         _ => Err(SeedExplorerError::UnsupportedCipher),
     }
 }
 
+/// move a passphrase into a sodoken secure buffer, zeroizing the original
+fn passphrase_buf(passphrase: String) -> SeedExplorerResult<sodoken::BufRead> {
+    secure_buf(Zeroizing::new(passphrase).as_bytes())
+}
+
+/// copy secret bytes into memory that is locked and zeroed on free
+fn secure_buf(bytes: &[u8]) -> SeedExplorerResult<sodoken::BufRead> {
+    let buf = sodoken::BufWrite::new_mem_locked(bytes.len())?;
+    buf.write_lock().copy_from_slice(bytes);
+    Ok(buf.to_read())
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum SeedExplorerError {
     #[error(transparent)]
@@ -151,10 +348,20 @@ pub enum SeedExplorerError {
     Ed25519Error(#[from] ed25519::Error),
     #[error(transparent)]
     DecodeError(#[from] base64::DecodeError),
+    #[error(transparent)]
+    Pkcs8Error(#[from] pkcs8::Error),
+    #[error(transparent)]
+    SpkiError(#[from] pkcs8::spki::Error),
+    #[error(transparent)]
+    SshKeyError(#[from] ssh_key::Error),
+    #[error(transparent)]
+    MnemonicError(#[from] bip39::Error),
     #[error("Seed hash unsupported cipher type")]
     UnsupportedCipher,
     #[error("Password required to unlock seed")]
     PasswordRequired,
+    #[error("Embedded public key does not match the secret key")]
+    PublicKeyMismatch,
     #[error("Generic Error: {0}")]
     Generic(String),
 }
@@ -0,0 +1,475 @@
+//! Command line access to the seed bundle helpers of this crate.
+
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::VerifyingKey;
+use hpos_config_core::{load, public_key, save, ConductorOptions, Config};
+use hpos_config_seed_bundle_explorer::{
+    decoded_to_ed25519_keypair, holoport_key,
+    import::{v1_config_from_keypair, v3_config_from_keypair},
+    inspect::inspect_bundle,
+    lair::{check_lair_root, export_for_lair, LairExport, LairVersion},
+    mnemonic::{bundle_to_mnemonic, master_bundle_from_mnemonic},
+    relock,
+    shamir::{combine_master_seed, split_master_seed},
+};
+use rand::rngs::OsRng;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::time::SystemTime;
+use structopt::StructOpt;
+use zeroize::Zeroizing;
+
+#[derive(StructOpt)]
+#[structopt(about = "Inspect and manipulate HoloPort seed bundles")]
+enum Cli {
+    /// Build a V1 or V3 config around a lair (<v0.0.6) keypair blob
+    ImportLair {
+        #[structopt(long = "blob")]
+        /// The base64 keypair blob written by hpos-config-seed-encoder
+        blob: String,
+        #[structopt(long = "blob-passphrase")]
+        /// The passphrase the blob was encrypted with, if any
+        blob_passphrase: Option<String>,
+        #[structopt(long = "config-version", default_value = "v3", possible_values = &["v1", "v3"])]
+        /// Version of the config to build
+        config_version: String,
+        #[structopt(long = "email")]
+        /// HoloPort admin email address
+        email: String,
+        #[structopt(long = "password")]
+        /// HoloPort admin password
+        password: String,
+        #[structopt(long = "registration-code")]
+        /// HoloPort registration code, required for v3
+        registration_code: Option<String>,
+        #[structopt(long = "derivation-path")]
+        /// Derivation path of the device seed, required for v3
+        derivation_path: Option<String>,
+        #[structopt(long = "revocation-key")]
+        /// Base64 revocation public key, defaults to the holoport key
+        revocation_key: Option<String>,
+        #[structopt(long = "device-bundle-passphrase")]
+        /// The passphrase to lock the new device bundle with, required for v3
+        device_bundle_passphrase: Option<String>,
+    },
+    /// Re-encrypt the device bundle of a config file under a new passphrase
+    Relock {
+        #[structopt(long = "config-path")]
+        /// The path to the hpos-config file, rewritten in place. Defaults to
+        /// $HPOS_CONFIG_PATH or a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "old-passphrase")]
+        /// The passphrase currently unlocking the device-bundle
+        old_passphrase: String,
+        #[structopt(long = "new-passphrase")]
+        /// The passphrase to lock the device-bundle with
+        new_passphrase: String,
+    },
+    /// Show the ciphers, pwhash limits and app data of a device bundle without unlocking it
+    InspectBundle {
+        #[structopt(long = "config-path")]
+        /// The path to the hpos-config file holding the device-bundle. Defaults
+        /// to $HPOS_CONFIG_PATH or a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "device-bundle", conflicts_with = "config_path")]
+        /// The base64 device-bundle itself
+        device_bundle: Option<String>,
+    },
+    /// Print the registration code of a config signed by its holoport key,
+    /// for authenticating to Holo services
+    RegistrationPayload {
+        #[structopt(long = "config-path")]
+        /// The path to the hpos-config file. Defaults to $HPOS_CONFIG_PATH or
+        /// a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "passphrase", default_value = "pass")]
+        /// The passphrase unlocking the device-bundle, Quickstart uses the default
+        passphrase: String,
+    },
+    /// Export the device seed for lair-keystore. lair (>=v0.1) takes a seed
+    /// bundle through `import-seed`, lair (<v0.0.6) a keypair blob
+    ExportLair {
+        #[structopt(long = "config-path")]
+        /// The path to the hpos-config file. Defaults to $HPOS_CONFIG_PATH or
+        /// a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "passphrase", default_value = "pass")]
+        /// The passphrase unlocking the device-bundle, Quickstart uses the default
+        passphrase: String,
+        #[structopt(long = "lair-version")]
+        /// The lair-keystore release to export for, e.g. 0.4.5
+        lair_version: LairVersion,
+        #[structopt(long = "export-passphrase")]
+        /// The passphrase locking the export, required for lair (>=v0.1) and
+        /// rejected for lair (<v0.0.6), which can't load locked keypairs
+        export_passphrase: Option<String>,
+        #[structopt(long = "tag", default_value = "hpos-host")]
+        /// Lair tag to import the seed under, lair (>=v0.1) only
+        tag: String,
+        #[structopt(long = "lair-root")]
+        /// Lair root to check against the selected version. For lair (>=v0.1)
+        /// the import command and the store it writes to are printed to
+        /// stderr, which needs --output
+        lair_root: Option<PathBuf>,
+        #[structopt(long = "output")]
+        /// Write the export to this new file, readable only by its owner,
+        /// instead of stdout. The import command reads the seed bundle from it
+        output: Option<PathBuf>,
+    },
+    /// Print the BIP39 mnemonic of the seed inside a seed bundle, such as
+    /// Quickstart's master-seed file or the device bundle of a config
+    ExportMnemonic {
+        #[structopt(long = "seed-file")]
+        /// A file holding a base64 seed bundle, like master-seed. Defaults to
+        /// the device bundle of the config
+        seed_file: Option<PathBuf>,
+        #[structopt(long = "config-path", conflicts_with = "seed_file")]
+        /// The path to the hpos-config file. Defaults to $HPOS_CONFIG_PATH or
+        /// a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "passphrase")]
+        /// The passphrase unlocking the seed bundle
+        passphrase: String,
+    },
+    /// Rebuild a locked master seed bundle from its BIP39 mnemonic, which is
+    /// read from stdin
+    ImportMnemonic {
+        #[structopt(long = "passphrase")]
+        /// The passphrase to lock the master seed bundle with
+        passphrase: String,
+        #[structopt(long = "output")]
+        /// Where to write the master seed bundle, stdout if not given
+        output: Option<PathBuf>,
+    },
+    /// Split the seed of a master seed bundle into shares, any `threshold` of
+    /// which rebuild it. Prints one share per line
+    Split {
+        #[structopt(long = "seed-file")]
+        /// A file holding the base64 master seed bundle, like master-seed
+        seed_file: PathBuf,
+        #[structopt(long = "passphrase")]
+        /// The passphrase unlocking the master seed bundle
+        passphrase: String,
+        #[structopt(long = "threshold")]
+        /// Number of shares needed to rebuild the master seed, at least 2
+        threshold: u8,
+        #[structopt(long = "shares")]
+        /// Number of shares to write, at most 255
+        shares: u8,
+    },
+    /// Rebuild a locked master seed bundle from shares written by `split`,
+    /// read from stdin one per line
+    Combine {
+        #[structopt(long = "passphrase")]
+        /// The passphrase to lock the master seed bundle with
+        passphrase: String,
+        #[structopt(long = "output")]
+        /// Where to write the master seed bundle, stdout if not given
+        output: Option<PathBuf>,
+    },
+    /// Write the Holochain conductor config and the script importing the
+    /// device bundle into lair
+    ConductorConfig {
+        #[structopt(long = "config-path")]
+        /// The path to the hpos-config file. Defaults to $HPOS_CONFIG_PATH or
+        /// a well-known location
+        config_path: Option<PathBuf>,
+        #[structopt(long = "passphrase", default_value = "pass")]
+        /// The passphrase unlocking the device-bundle, Quickstart uses the default
+        passphrase: String,
+        #[structopt(long = "output-dir", default_value = ".")]
+        /// Where to write conductor-config.yaml and lair-import.sh
+        output_dir: PathBuf,
+        #[structopt(
+            long = "data-root-path",
+            default_value = "/var/lib/holochain-rsm/databases"
+        )]
+        /// Conductor database directory
+        data_root_path: PathBuf,
+        #[structopt(
+            long = "keystore-path",
+            default_value = "/var/lib/holochain-rsm/lair-keystore"
+        )]
+        /// Lair keystore directory
+        keystore_path: PathBuf,
+        #[structopt(long = "admin-port", default_value = "4444")]
+        /// Conductor admin websocket port
+        admin_port: u16,
+        #[structopt(long = "seed-tag", default_value = "hpos-host")]
+        /// Lair tag to import the device bundle under
+        seed_tag: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    match Cli::from_args() {
+        Cli::ImportLair {
+            blob,
+            blob_passphrase,
+            config_version,
+            email,
+            password,
+            registration_code,
+            derivation_path,
+            revocation_key,
+            device_bundle_passphrase,
+        } => {
+            let secret = decoded_to_ed25519_keypair(&blob, blob_passphrase)
+                .await
+                .context("unable to decode the lair keypair blob")?;
+            let config = match config_version.as_str() {
+                "v1" => v1_config_from_keypair(&secret, email, password)?,
+                "v3" => {
+                    let revocation_pub_key = revocation_key
+                        .as_deref()
+                        .map(parse_public_key)
+                        .transpose()?;
+                    v3_config_from_keypair(
+                        &secret,
+                        device_bundle_passphrase
+                            .context("--device-bundle-passphrase is required for v3")?,
+                        email,
+                        password,
+                        registration_code.context("--registration-code is required for v3")?,
+                        derivation_path.context("--derivation-path is required for v3")?,
+                        revocation_pub_key,
+                    )
+                    .await?
+                }
+                other => bail!("unsupported config version {}", other),
+            };
+            println!("{}", serde_json::to_string_pretty(&config)?);
+        }
+        Cli::Relock {
+            config_path,
+            old_passphrase,
+            new_passphrase,
+        } => {
+            let (mut config, config_path) = load(config_path.as_deref())?;
+            match &mut config {
+                Config::V1 { .. } => bail!("V1 configs do not carry a device bundle"),
+                Config::V2 { device_bundle, .. }
+                | Config::V3 { device_bundle, .. }
+                | Config::V4 { device_bundle, .. } => {
+                    *device_bundle = relock(device_bundle, old_passphrase, new_passphrase)
+                        .await
+                        .context(format!(
+                            "unable to unlock the device bundle from {}",
+                            &config_path.to_string_lossy()
+                        ))?;
+                }
+            }
+            save(&config, &config_path).context(format!(
+                "failed to write file {}",
+                &config_path.to_string_lossy()
+            ))?;
+        }
+        Cli::InspectBundle {
+            config_path,
+            device_bundle,
+        } => {
+            let device_bundle = match device_bundle {
+                Some(device_bundle) => device_bundle,
+                None => match load(config_path.as_deref())?.0 {
+                    Config::V1 { .. } => bail!("V1 configs do not carry a device bundle"),
+                    Config::V2 { device_bundle, .. }
+                    | Config::V3 { device_bundle, .. }
+                    | Config::V4 { device_bundle, .. } => device_bundle,
+                },
+            };
+            let info = inspect_bundle(&device_bundle)?;
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        }
+        Cli::RegistrationPayload {
+            config_path,
+            passphrase,
+        } => {
+            let (config, config_path) = load(config_path.as_deref())?;
+            let holoport_key = holoport_key(&config, Some(passphrase))
+                .await
+                .context(format!(
+                    "unable to unlock the device bundle from {}",
+                    &config_path.to_string_lossy()
+                ))?;
+            let payload = config
+                .registration_payload(&holoport_key, SystemTime::now())
+                .map_err(|e| anyhow!("{}", e))?;
+            println!("{}", serde_json::to_string_pretty(&payload)?);
+        }
+        Cli::ExportLair {
+            config_path,
+            passphrase,
+            lair_version,
+            export_passphrase,
+            tag,
+            lair_root,
+            output,
+        } => {
+            if lair_root.is_some() && output.is_none() && lair_version == LairVersion::SeedBundle {
+                bail!(
+                    "--lair-root needs --output, the import command reads the seed bundle from it"
+                );
+            }
+            let lair_config = match &lair_root {
+                Some(lair_root) => check_lair_root(lair_root, lair_version)?,
+                None => None,
+            };
+            let (config, config_path) = load(config_path.as_deref())?;
+            let secret = holoport_key(&config, Some(passphrase))
+                .await
+                .context(format!(
+                    "unable to unlock the device bundle from {}",
+                    &config_path.to_string_lossy()
+                ))?;
+            let export = export_for_lair(&secret, lair_version, tag, export_passphrase).await?;
+            match (&output, &export) {
+                (Some(output), _) => export.write(output)?,
+                (None, LairExport::Keypair(blob)) => println!("{}", blob),
+                (None, LairExport::SeedBundle { seed_bundle, .. }) => println!("{}", seed_bundle),
+            }
+            let command = lair_root
+                .zip(output)
+                .and_then(|(root, output)| export.import_command(&root, &output));
+            if let Some(command) = command {
+                if let Some(lair_config) = lair_config {
+                    eprintln!(
+                        "# imports into {}",
+                        lair_config.store_file.to_string_lossy()
+                    );
+                }
+                eprintln!("{}", command);
+            }
+        }
+        Cli::ExportMnemonic {
+            seed_file,
+            config_path,
+            passphrase,
+        } => {
+            let seed_bundle = match seed_file {
+                Some(seed_file) => fs::read_to_string(&seed_file)
+                    .context(format!(
+                        "failed to read file {}",
+                        seed_file.to_string_lossy()
+                    ))?
+                    .trim()
+                    .to_string(),
+                None => match load(config_path.as_deref())?.0 {
+                    Config::V1 { .. } => bail!("V1 configs do not carry a device bundle"),
+                    Config::V2 { device_bundle, .. }
+                    | Config::V3 { device_bundle, .. }
+                    | Config::V4 { device_bundle, .. } => device_bundle,
+                },
+            };
+            let mnemonic = bundle_to_mnemonic(&seed_bundle, Some(passphrase))
+                .await
+                .context("unable to unlock the seed bundle")?;
+            println!("{}", *mnemonic);
+        }
+        Cli::ImportMnemonic { passphrase, output } => {
+            // kept off the command line so it doesn't end up in shell history
+            let mut mnemonic = Zeroizing::new(String::new());
+            io::stdin()
+                .read_to_string(&mut mnemonic)
+                .context("failed to read the mnemonic from stdin")?;
+            let master_seed = master_bundle_from_mnemonic(&mnemonic, passphrase).await?;
+            match output {
+                Some(output) => fs::write(&output, master_seed)
+                    .context(format!("failed to write file {}", output.to_string_lossy()))?,
+                None => println!("{}", master_seed),
+            }
+        }
+        Cli::Split {
+            seed_file,
+            passphrase,
+            threshold,
+            shares,
+        } => {
+            let master_seed = fs::read_to_string(&seed_file).context(format!(
+                "failed to read file {}",
+                seed_file.to_string_lossy()
+            ))?;
+            let shares = split_master_seed(
+                &master_seed.trim().to_string(),
+                passphrase,
+                threshold,
+                shares,
+                &mut OsRng,
+            )
+            .await?;
+            for share in shares {
+                println!("{}", *share);
+            }
+        }
+        Cli::Combine { passphrase, output } => {
+            let mut shares = Zeroizing::new(String::new());
+            io::stdin()
+                .read_to_string(&mut shares)
+                .context("failed to read the shares from stdin")?;
+            let shares: Vec<&str> = shares
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect();
+            let master_seed = combine_master_seed(&shares, passphrase).await?;
+            match output {
+                Some(output) => fs::write(&output, master_seed)
+                    .context(format!("failed to write file {}", output.to_string_lossy()))?,
+                None => println!("{}", master_seed),
+            }
+        }
+        Cli::ConductorConfig {
+            config_path,
+            passphrase,
+            output_dir,
+            data_root_path,
+            keystore_path,
+            admin_port,
+            seed_tag,
+        } => {
+            let (config, config_path) = load(config_path.as_deref())?;
+            let options = ConductorOptions {
+                data_root_path,
+                keystore_path,
+                admin_port,
+                seed_tag,
+            };
+            let setup = config
+                .conductor_setup(&options)
+                .map_err(|e| anyhow!("{}", e))?;
+            // otherwise lair imports a different agent key than the one the
+            // conductor config is written for
+            let holoport_key = holoport_key(&config, Some(passphrase))
+                .await
+                .context(format!(
+                    "unable to unlock the device bundle from {}",
+                    &config_path.to_string_lossy()
+                ))?;
+            if let Config::V3 { holoport_id, .. } | Config::V4 { holoport_id, .. } = &config {
+                if public_key::to_base36_id(&holoport_key.verifying_key()) != *holoport_id {
+                    bail!("device bundle does not belong to holoport {}", holoport_id);
+                }
+            }
+            for (name, contents) in [
+                ("conductor-config.yaml", setup.conductor_config),
+                ("lair-import.sh", setup.lair_import),
+            ] {
+                let path = output_dir.join(name);
+                fs::write(&path, contents)
+                    .context(format!("failed to write file {}", path.to_string_lossy()))?;
+                println!("{}", path.to_string_lossy());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_public_key(key: &str) -> Result<VerifyingKey> {
+    let bytes = base64::decode_config(key, base64::STANDARD_NO_PAD)?;
+    let bytes: [u8; 32] = bytes[..]
+        .try_into()
+        .context("public key is not 32 bytes long")?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
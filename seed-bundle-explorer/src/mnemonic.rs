@@ -0,0 +1,44 @@
+//! BIP39 mnemonics of the seed inside a seed bundle, so owners can write down
+//! the seed instead of keeping Quickstart's `master-seed` file safe.
+//!
+//! Seed bundles hold 32 byte seeds, which are always 24 English words.
+
+use crate::{lock_master_seed, unlock_bundle, SeedExplorerError, SeedExplorerResult, SEED_LEN};
+use bip39::{Language, Mnemonic};
+use zeroize::Zeroizing;
+
+/// Unlock a base64 seed bundle and spell its seed as a mnemonic
+pub async fn bundle_to_mnemonic(
+    seed_bundle: &String,
+    passphrase: Option<String>,
+) -> SeedExplorerResult<Zeroizing<String>> {
+    let bundle = unlock_bundle(seed_bundle, passphrase).await?;
+    let seed = bundle.get_seed();
+    let mnemonic = Mnemonic::from_entropy_in(Language::English, &seed.read_lock()[..])?;
+    Ok(Zeroizing::new(mnemonic.to_string()))
+}
+
+/// Decode a mnemonic into the seed, checking its word list and checksum.
+/// Case and whitespace between words are ignored.
+pub fn seed_from_mnemonic(mnemonic: &str) -> SeedExplorerResult<Zeroizing<[u8; SEED_LEN]>> {
+    let mnemonic = Zeroizing::new(mnemonic.to_lowercase());
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, &mnemonic)?;
+    let entropy = Zeroizing::new(mnemonic.to_entropy());
+    let seed: [u8; SEED_LEN] = entropy[..].try_into().map_err(|_| {
+        SeedExplorerError::Generic(format!(
+            "Mnemonic holds a {} byte seed, seed bundles hold {} bytes (24 words)",
+            entropy.len(),
+            SEED_LEN
+        ))
+    })?;
+    Ok(Zeroizing::new(seed))
+}
+
+/// Rebuild a master seed bundle from its mnemonic, see `lock_master_seed`
+pub async fn master_bundle_from_mnemonic(
+    mnemonic: &str,
+    passphrase: String,
+) -> SeedExplorerResult<String> {
+    let seed = seed_from_mnemonic(mnemonic)?;
+    lock_master_seed(&seed, passphrase).await
+}
@@ -0,0 +1,285 @@
+//! k-of-n Shamir secret sharing of the seed inside a master seed bundle, so
+//! that no single backup gives away the master seed, and with it the
+//! revocation key derived at /0.
+//!
+//! The shared secret is the seed followed by a digest of it, the first 4 bytes
+//! of a personalized BLAKE2b-256 hash. Every secret byte is the constant term
+//! of its own random polynomial of degree k - 1 over GF(2^8), and share i holds
+//! the polynomials evaluated at i. As the digest is shared along with the seed,
+//! fewer than k shares reveal nothing about either, and it confirms the
+//! combined seed, since any k shares interpolate to some secret.
+//!
+//! A share is encoded as `version || threshold || index || split id || values || checksum`,
+//! base64 url safe without padding. The split id is random and independent of
+//! the seed, it tells shares of different splits apart before combining. The
+//! checksum is the first 4 bytes of the BLAKE2b-256 hash of the rest and
+//! catches transcription errors.
+
+use crate::{lock_master_seed, unlock, SeedExplorerError, SeedExplorerResult, SEED_LEN};
+use rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+const SHARE_VERSION: u8 = 3;
+const SPLIT_ID_LEN: usize = 4;
+const DIGEST_LEN: usize = 4;
+const DIGEST_PERSONAL: &[u8] = b"hpos-shamir-seed";
+/// The seed followed by its digest
+const SECRET_LEN: usize = SEED_LEN + DIGEST_LEN;
+const CHECKSUM_LEN: usize = 4;
+const SHARE_LEN: usize = 3 + SPLIT_ID_LEN + SECRET_LEN + CHECKSUM_LEN;
+
+/// One share of a split seed, zeroized on drop
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Share {
+    /// Number of shares needed to combine the seed
+    pub threshold: u8,
+    /// x coordinate of the share, from 1 on
+    pub index: u8,
+    split_id: [u8; SPLIT_ID_LEN],
+    values: [u8; SECRET_LEN],
+}
+
+impl Share {
+    pub fn encode(&self) -> Zeroizing<String> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(SHARE_LEN));
+        bytes.extend([SHARE_VERSION, self.threshold, self.index]);
+        bytes.extend(self.split_id);
+        bytes.extend(self.values);
+        let sum = checksum(&bytes);
+        bytes.extend(sum);
+        Zeroizing::new(base64::encode_config(&*bytes, base64::URL_SAFE_NO_PAD))
+    }
+
+    pub fn decode(share: &str) -> SeedExplorerResult<Share> {
+        let bytes = Zeroizing::new(base64::decode_config(
+            share.trim(),
+            base64::URL_SAFE_NO_PAD,
+        )?);
+        if bytes.len() != SHARE_LEN {
+            return Err(SeedExplorerError::Generic(format!(
+                "Share is {} bytes long, expected {}",
+                bytes.len(),
+                SHARE_LEN
+            )));
+        }
+        let (body, sum) = bytes.split_at(SHARE_LEN - CHECKSUM_LEN);
+        if checksum(body) != sum {
+            return Err(SeedExplorerError::Generic(
+                "Share checksum does not match, was it copied correctly?".into(),
+            ));
+        }
+        if body[0] != SHARE_VERSION {
+            return Err(SeedExplorerError::Generic(format!(
+                "Unsupported share version {}",
+                body[0]
+            )));
+        }
+        let share = Share {
+            threshold: body[1],
+            index: body[2],
+            split_id: body[3..3 + SPLIT_ID_LEN]
+                .try_into()
+                .expect("checked length"),
+            values: body[3 + SPLIT_ID_LEN..].try_into().expect("checked length"),
+        };
+        if share.threshold < 2 || share.index == 0 {
+            return Err(SeedExplorerError::Generic(
+                "Share has an invalid threshold or index".into(),
+            ));
+        }
+        Ok(share)
+    }
+}
+
+/// Split `seed` into `shares` shares, any `threshold` of which combine it
+pub fn split_seed<R: RngCore + CryptoRng>(
+    seed: &[u8; SEED_LEN],
+    threshold: u8,
+    shares: u8,
+    rng: &mut R,
+) -> SeedExplorerResult<Vec<Share>> {
+    if threshold < 2 || threshold > shares {
+        return Err(SeedExplorerError::Generic(format!(
+            "Threshold must be between 2 and the number of shares, got {} of {}",
+            threshold, shares
+        )));
+    }
+    // coefficients[d][b] is the degree d coefficient of the polynomial for secret byte b
+    let mut coefficients = Zeroizing::new(vec![[0u8; SECRET_LEN]; threshold as usize]);
+    coefficients[0][..SEED_LEN].copy_from_slice(seed);
+    coefficients[0][SEED_LEN..].copy_from_slice(&seed_digest(seed));
+    for coefficient in coefficients[1..].iter_mut() {
+        rng.fill_bytes(coefficient);
+    }
+    // a zero leading coefficient would let threshold - 1 shares recover that byte
+    let leading = threshold as usize - 1;
+    for byte in 0..SECRET_LEN {
+        while coefficients[leading][byte] == 0 {
+            let mut replacement = [0u8; 1];
+            rng.fill_bytes(&mut replacement);
+            coefficients[leading][byte] = replacement[0];
+        }
+    }
+
+    let mut split_id = [0u8; SPLIT_ID_LEN];
+    rng.fill_bytes(&mut split_id);
+
+    Ok((1..=shares)
+        .map(|index| {
+            let mut values = [0u8; SECRET_LEN];
+            for (byte, value) in values.iter_mut().enumerate() {
+                *value = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, coefficient| gf_mul(acc, index) ^ coefficient[byte]);
+            }
+            Share {
+                threshold,
+                index,
+                split_id,
+                values,
+            }
+        })
+        .collect())
+}
+
+/// Combine the seed from at least `threshold` shares of the same split.
+/// Shares beyond the threshold are checked against the first ones, and the
+/// combined seed against the digest combined with it.
+pub fn combine_seed(shares: &[Share]) -> SeedExplorerResult<Zeroizing<[u8; SEED_LEN]>> {
+    let (threshold, split_id) = match shares.first() {
+        Some(share) => (share.threshold, share.split_id),
+        None => return Err(SeedExplorerError::Generic("No shares given".into())),
+    };
+    for (i, share) in shares.iter().enumerate() {
+        if share.threshold != threshold {
+            return Err(SeedExplorerError::Generic(
+                "Shares come from splits with different thresholds".into(),
+            ));
+        }
+        if share.split_id != split_id {
+            return Err(SeedExplorerError::Generic(
+                "Shares come from different splits".into(),
+            ));
+        }
+        if shares[..i].iter().any(|other| other.index == share.index) {
+            return Err(SeedExplorerError::Generic(format!(
+                "Share {} is given more than once",
+                share.index
+            )));
+        }
+    }
+    if shares.len() < threshold as usize {
+        return Err(SeedExplorerError::Generic(format!(
+            "{} shares are needed, got {}",
+            threshold,
+            shares.len()
+        )));
+    }
+
+    let (points, extra) = shares.split_at(threshold as usize);
+    for share in extra {
+        if *interpolate(points, share.index) != share.values {
+            return Err(SeedExplorerError::Generic(format!(
+                "Share {} does not belong to the same split as the others",
+                share.index
+            )));
+        }
+    }
+    let secret = interpolate(points, 0);
+    let seed = Zeroizing::new(secret[..SEED_LEN].try_into().expect("secret holds a seed"));
+    if seed_digest(&seed) != secret[SEED_LEN..] {
+        return Err(SeedExplorerError::Generic(
+            "Shares do not combine to the seed they were split from".into(),
+        ));
+    }
+    Ok(seed)
+}
+
+/// Unlock a master seed bundle and split its seed into encoded shares
+pub async fn split_master_seed<R: RngCore + CryptoRng>(
+    master_seed: &String,
+    passphrase: String,
+    threshold: u8,
+    shares: u8,
+    rng: &mut R,
+) -> SeedExplorerResult<Vec<Zeroizing<String>>> {
+    let seed = Zeroizing::new(unlock(master_seed, Some(passphrase)).await?.to_bytes());
+    Ok(split_seed(&seed, threshold, shares, rng)?
+        .iter()
+        .map(Share::encode)
+        .collect())
+}
+
+/// Combine encoded shares into a master seed bundle, see `lock_master_seed`
+pub async fn combine_master_seed(
+    shares: &[&str],
+    passphrase: String,
+) -> SeedExplorerResult<String> {
+    let shares = shares
+        .iter()
+        .map(|share| Share::decode(share))
+        .collect::<SeedExplorerResult<Vec<_>>>()?;
+    let seed = combine_seed(&shares)?;
+    lock_master_seed(&seed, passphrase).await
+}
+
+/// Lagrange interpolation of the polynomials through `points` at `x`
+fn interpolate(points: &[Share], x: u8) -> Zeroizing<[u8; SECRET_LEN]> {
+    let mut values = Zeroizing::new([0u8; SECRET_LEN]);
+    for (i, point) in points.iter().enumerate() {
+        // subtraction is xor in GF(2^8)
+        let mut basis = 1;
+        for (j, other) in points.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(
+                    basis,
+                    gf_mul(x ^ other.index, gf_inv(point.index ^ other.index)),
+                );
+            }
+        }
+        for (value, y) in values.iter_mut().zip(point.values) {
+            *value ^= gf_mul(y, basis);
+        }
+    }
+    values
+}
+
+/// Multiplication modulo x^8 + x^4 + x^3 + x + 1, without secret dependent branches
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        a = (a << 1) ^ (0x1b & (a >> 7).wrapping_neg());
+        b >>= 1;
+    }
+    product
+}
+
+/// a^254, the inverse of every non-zero a
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    for _ in 0..7 {
+        power = gf_mul(power, power);
+        result = gf_mul(result, power);
+    }
+    result
+}
+
+fn seed_digest(seed: &[u8; SEED_LEN]) -> [u8; DIGEST_LEN] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(DIGEST_PERSONAL)
+        .hash(seed);
+    hash.as_bytes()[..DIGEST_LEN]
+        .try_into()
+        .expect("hash is longer than the digest")
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = blake2b_simd::Params::new().hash_length(32).hash(bytes);
+    hash.as_bytes()[..CHECKSUM_LEN]
+        .try_into()
+        .expect("hash is longer than the checksum")
+}
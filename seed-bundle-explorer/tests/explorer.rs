@@ -1,8 +1,21 @@
 #[cfg(test)]
 mod tests {
-    use ed25519_dalek::VerifyingKey;
+    use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+    use ed25519_dalek::{SigningKey, VerifyingKey};
     use hpos_config_core::Config;
-    use hpos_config_seed_bundle_explorer::holoport_public_key;
+    use hpos_config_seed_bundle_explorer::{
+        decoded_to_ed25519_keypair, encoded_ed25519_keypair, encrypt_key,
+        export::{export_key, KeyFormat},
+        holoport_key, holoport_public_key,
+        import::v3_config_from_keypair,
+        inspect::{inspect_bundle, CipherInfo},
+        lair::{check_lair_root, export_for_lair, LairExport, LairVersion, LAIR_CONFIG_FILE},
+        mnemonic::{bundle_to_mnemonic, master_bundle_from_mnemonic, seed_from_mnemonic},
+        relock,
+        shamir::{combine_master_seed, combine_seed, split_master_seed, split_seed, Share},
+        unlock, SeedExplorerError,
+    };
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
 
     #[tokio::test(flavor = "multi_thread")]
     async fn get_sign_pub_key() -> Result<(), String> {
@@ -14,12 +27,480 @@ mod tests {
         assert_eq!(pub_key, get_mock_pub_key()?);
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn encrypted_keypair_round_trip() -> Result<(), String> {
+        let config: Config = get_mock_config()?;
+        // a config whose device bundle was locked with another passphrase
+        assert!(holoport_key(&config, Some("wrong".to_string()))
+            .await
+            .is_err());
+        let secret = holoport_key(&config, Some("pass".to_string()))
+            .await
+            .unwrap();
+        let blob = encoded_ed25519_keypair(
+            &config,
+            Some("pass".to_string()),
+            Some("export-passphrase".to_string()),
+        )
+        .await
+        .unwrap();
+
+        assert!(decoded_to_ed25519_keypair(&blob, None).await.is_err());
+        assert!(decoded_to_ed25519_keypair(&blob, Some("wrong".to_string()))
+            .await
+            .is_err());
+        let decoded = decoded_to_ed25519_keypair(&blob, Some("export-passphrase".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(decoded.to_bytes(), secret.to_bytes());
+
+        let plaintext = encoded_ed25519_keypair(&config, Some("pass".to_string()), None)
+            .await
+            .unwrap();
+        let decoded = decoded_to_ed25519_keypair(&plaintext, None).await.unwrap();
+        assert_eq!(decoded.to_bytes(), secret.to_bytes());
+        Ok(())
+    }
+    #[tokio::test(flavor = "multi_thread")]
+    async fn export_standard_formats() -> Result<(), String> {
+        let config: Config = get_mock_config()?;
+        let secret = holoport_key(&config, Some("pass".to_string()))
+            .await
+            .unwrap();
+
+        let pem = export_key(&secret, KeyFormat::Pem, None).await.unwrap();
+        let recovered = SigningKey::from_pkcs8_pem(&pem.private_key).unwrap();
+        assert_eq!(recovered.to_bytes(), secret.to_bytes());
+        let public_key =
+            VerifyingKey::from_public_key_pem(pem.public_key.as_ref().unwrap()).unwrap();
+        assert_eq!(public_key, secret.verifying_key());
+
+        let pem = export_key(&secret, KeyFormat::Pem, Some("export".to_string()))
+            .await
+            .unwrap();
+        assert!(SigningKey::from_pkcs8_pem(&pem.private_key).is_err());
+        let recovered = SigningKey::from_pkcs8_encrypted_pem(&pem.private_key, "export").unwrap();
+        assert_eq!(recovered.to_bytes(), secret.to_bytes());
+
+        for passphrase in [None, Some("export")] {
+            let ssh = export_key(&secret, KeyFormat::OpenSsh, passphrase.map(String::from))
+                .await
+                .unwrap();
+            let mut private_key = ssh_key::PrivateKey::from_openssh(&ssh.private_key).unwrap();
+            assert_eq!(private_key.is_encrypted(), passphrase.is_some());
+            if let Some(passphrase) = passphrase {
+                assert!(private_key.decrypt("wrong").is_err());
+                private_key = private_key.decrypt(passphrase).unwrap();
+            }
+            let keypair = private_key.key_data().ed25519().unwrap();
+            assert_eq!(keypair.private.to_bytes(), secret.to_bytes());
+            assert_eq!(keypair.public.0, secret.verifying_key().to_bytes());
+            let public_key =
+                ssh_key::PublicKey::from_openssh(ssh.public_key.as_ref().unwrap()).unwrap();
+            assert_eq!(
+                public_key.key_data().ed25519().unwrap().0,
+                secret.verifying_key().to_bytes()
+            );
+        }
+
+        let jwk = export_key(&secret, KeyFormat::Jwk, None).await.unwrap();
+        let private_jwk: serde_json::Value = serde_json::from_str(&jwk.private_key).unwrap();
+        let public_jwk: serde_json::Value =
+            serde_json::from_str(jwk.public_key.as_ref().unwrap()).unwrap();
+        assert_eq!(private_jwk["kty"], "OKP");
+        assert_eq!(private_jwk["crv"], "Ed25519");
+        assert!(public_jwk.get("d").is_none());
+        let jwk_bytes = |value: &serde_json::Value| {
+            base64::decode_config(value.as_str().unwrap(), base64::URL_SAFE_NO_PAD).unwrap()
+        };
+        assert_eq!(jwk_bytes(&private_jwk["d"]), secret.to_bytes());
+        assert_eq!(
+            jwk_bytes(&private_jwk["x"]),
+            secret.verifying_key().to_bytes()
+        );
+        assert_eq!(
+            jwk_bytes(&public_jwk["x"]),
+            secret.verifying_key().to_bytes()
+        );
+        assert!(
+            export_key(&secret, KeyFormat::Jwk, Some("export".to_string()))
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn import_lair_keypair() -> Result<(), String> {
+        let config: Config = get_mock_config()?;
+        let secret = holoport_key(&config, Some("pass".to_string()))
+            .await
+            .unwrap();
+
+        let mut tampered = base64::decode(encrypt_key(&secret, &secret.verifying_key())).unwrap();
+        tampered[32] ^= 1;
+        assert!(matches!(
+            decoded_to_ed25519_keypair(&base64::encode(&tampered), None).await,
+            Err(SeedExplorerError::PublicKeyMismatch)
+        ));
+        let mut tampered = base64::decode(encrypt_key(&secret, &secret.verifying_key())).unwrap();
+        tampered[0] = 2;
+        assert!(decoded_to_ed25519_keypair(&base64::encode(&tampered), None)
+            .await
+            .is_err());
+
+        let blob = encrypt_key(&secret, &secret.verifying_key());
+        let recovered = decoded_to_ed25519_keypair(&blob, None).await.unwrap();
+        let imported = v3_config_from_keypair(
+            &recovered,
+            "new-pass".to_string(),
+            "jack@holo.host".to_string(),
+            "password".to_string(),
+            "registration-code".to_string(),
+            "1".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            holoport_public_key(&imported, None).await.unwrap(),
+            secret.verifying_key()
+        );
+        let unlocked = holoport_key(&imported, Some("new-pass".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(unlocked.to_bytes(), secret.to_bytes());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn relock_device_bundle() -> Result<(), String> {
+        let device_bundle = get_mock_device_bundle();
+        let secret = unlock(&device_bundle, Some("pass".to_string()))
+            .await
+            .unwrap();
+
+        let relocked = relock(
+            &device_bundle,
+            "pass".to_string(),
+            "new-passphrase".to_string(),
+        )
+        .await
+        .unwrap();
+        assert!(unlock(&relocked, Some("pass".to_string())).await.is_err());
+        let unlocked = unlock(&relocked, Some("new-passphrase".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(unlocked.to_bytes(), secret.to_bytes());
+        assert_eq!(
+            inspect_bundle(&relocked).unwrap().app_data,
+            inspect_bundle(&device_bundle).unwrap().app_data
+        );
+        assert!(
+            relock(&device_bundle, "wrong".to_string(), "new".to_string())
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn inspect_quickstart_bundle() {
+        let info = inspect_bundle(&get_mock_device_bundle()).unwrap();
+        assert_eq!(info.format, "hcsb0");
+        assert!(matches!(
+            info.ciphers.as_slice(),
+            [CipherInfo::PwHash {
+                mem_limit: 8192,
+                ops_limit: 1,
+                limits: Some(limits),
+            }] if limits == "minimum"
+        ));
+        assert_eq!(info.app_data["device_number"], 0);
+        assert_eq!(info.app_data["generate_by"], "quickstart-v2.0");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn export_for_lair_versions() -> Result<(), String> {
+        let config: Config = get_mock_config()?;
+        let secret = holoport_key(&config, Some("pass".to_string()))
+            .await
+            .unwrap();
+        assert_eq!("0.0.5".parse::<LairVersion>().unwrap(), LairVersion::Legacy);
+        assert_eq!(
+            "v0.4.5".parse::<LairVersion>().unwrap(),
+            LairVersion::SeedBundle
+        );
+        assert_eq!(
+            "1.0".parse::<LairVersion>().unwrap(),
+            LairVersion::SeedBundle
+        );
+        assert!("0.0.9".parse::<LairVersion>().is_err());
+        assert!("latest".parse::<LairVersion>().is_err());
+
+        let lair_root =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/lair-root");
+        let lair_config = check_lair_root(&lair_root, LairVersion::SeedBundle)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            lair_config.store_file,
+            std::path::Path::new("/var/lib/holochain-rsm/lair-keystore/store_file")
+        );
+        assert!(check_lair_root(&lair_root, LairVersion::Legacy).is_err());
+        assert!(check_lair_root(&lair_root.join("missing"), LairVersion::SeedBundle).is_err());
+
+        let bad_root = std::env::temp_dir().join(format!("lair-root-{}", std::process::id()));
+        std::fs::create_dir_all(&bad_root).unwrap();
+        let fixture = std::fs::read_to_string(lair_root.join(LAIR_CONFIG_FILE)).unwrap();
+        for (from, to) in [
+            ("connectionUrl: \"unix://", "connectionUrl: \"http://"),
+            (
+                "storeFile: /var/lib/holochain-rsm/lair-keystore/",
+                "storeFile: ",
+            ),
+            // yaml does not allow tabs, and a missing field
+            ("pidFile:", "\tpidFile:"),
+            ("storeFile:", "# storeFile:"),
+        ] {
+            std::fs::write(bad_root.join(LAIR_CONFIG_FILE), fixture.replace(from, to)).unwrap();
+            let err = check_lair_root(&bad_root, LairVersion::SeedBundle).unwrap_err();
+            assert!(err.to_string().contains("is not a"), "{}", err);
+        }
+        std::fs::remove_dir_all(&bad_root).unwrap();
+
+        match export_for_lair(&secret, LairVersion::Legacy, "hpos-host".to_string(), None)
+            .await
+            .unwrap()
+        {
+            LairExport::Keypair(blob) => {
+                assert_eq!(blob, encrypt_key(&secret, &secret.verifying_key()))
+            }
+            other => panic!("expected a keypair blob, got {:?}", other),
+        }
+        assert!(export_for_lair(
+            &secret,
+            LairVersion::Legacy,
+            "hpos-host".to_string(),
+            Some("lair-pass".to_string())
+        )
+        .await
+        .is_err());
+
+        assert!(matches!(
+            export_for_lair(
+                &secret,
+                LairVersion::SeedBundle,
+                "hpos-host".to_string(),
+                None
+            )
+            .await,
+            Err(SeedExplorerError::PasswordRequired)
+        ));
+        let export = export_for_lair(
+            &secret,
+            LairVersion::SeedBundle,
+            "hpos-host".to_string(),
+            Some("lair-pass".to_string()),
+        )
+        .await
+        .unwrap();
+        let seed_bundle = match &export {
+            LairExport::SeedBundle { tag, seed_bundle } => {
+                assert_eq!(tag, "hpos-host");
+                seed_bundle.clone()
+            }
+            other => panic!("expected a seed bundle, got {:?}", other),
+        };
+        assert!(matches!(
+            inspect_bundle(&seed_bundle).unwrap().ciphers.as_slice(),
+            [CipherInfo::PwHash { limits: Some(limits), .. }] if limits == "minimum"
+        ));
+        assert!(unlock(&seed_bundle, Some("wrong".to_string()))
+            .await
+            .is_err());
+        let unlocked = unlock(&seed_bundle, Some("lair-pass".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(unlocked.to_bytes(), secret.to_bytes());
+
+        let seed_bundle_file =
+            std::env::temp_dir().join(format!("lair-seed-bundle-{}", std::process::id()));
+        export.write(&seed_bundle_file).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&seed_bundle_file).unwrap().trim(),
+            seed_bundle
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&seed_bundle_file)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert!(export.write(&seed_bundle_file).is_err());
+        std::fs::remove_file(&seed_bundle_file).unwrap();
+
+        let command = export
+            .import_command(&lair_root, &seed_bundle_file)
+            .unwrap();
+        assert_eq!(
+            command,
+            format!(
+                "lair-keystore --lair-root '{}' import-seed --piped 'hpos-host' \"$(cat '{}')\"",
+                lair_root.to_string_lossy(),
+                seed_bundle_file.to_string_lossy()
+            )
+        );
+        assert!(!command.contains(&seed_bundle));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn mnemonic_round_trip() -> Result<(), String> {
+        // BIP39 test vector for 32 zero bytes
+        let zero = format!("{} art", ["abandon"; 23].join(" "));
+        assert_eq!(*seed_from_mnemonic(&zero).unwrap(), [0; 32]);
+        assert_eq!(
+            *seed_from_mnemonic(&format!("  {}\n", zero.to_uppercase())).unwrap(),
+            [0; 32]
+        );
+        assert!(matches!(
+            seed_from_mnemonic(&["abandon"; 24].join(" ")),
+            Err(SeedExplorerError::MnemonicError(
+                bip39::Error::InvalidChecksum
+            ))
+        ));
+        let twelve = format!("{} about", ["abandon"; 11].join(" "));
+        assert!(seed_from_mnemonic(&twelve).is_err());
+
+        let device_bundle = get_mock_device_bundle();
+        let secret = unlock(&device_bundle, Some("pass".to_string()))
+            .await
+            .unwrap();
+        let mnemonic = bundle_to_mnemonic(&device_bundle, Some("pass".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(mnemonic.split_whitespace().count(), 24);
+        assert_eq!(*seed_from_mnemonic(&mnemonic).unwrap(), secret.to_bytes());
+
+        let master_seed = master_bundle_from_mnemonic(&mnemonic, "master-pass".to_string())
+            .await
+            .unwrap();
+        let info = inspect_bundle(&master_seed).unwrap();
+        assert_eq!(info.app_data["bundleType"], "master");
+        assert!(matches!(
+            info.ciphers.as_slice(),
+            [CipherInfo::PwHash { limits: Some(limits), .. }] if limits == "minimum"
+        ));
+        assert_eq!(
+            *bundle_to_mnemonic(&master_seed, Some("master-pass".to_string()))
+                .await
+                .unwrap(),
+            *mnemonic
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shamir_split_is_deterministic() {
+        let seed: [u8; 32] = core::array::from_fn(|i| i as u8 * 7 + 1);
+        let encode = |shares: Vec<Share>| -> Vec<String> {
+            shares
+                .iter()
+                .map(|share| share.encode().to_string())
+                .collect()
+        };
+        let shares = encode(split_seed(&seed, 3, 5, &mut ChaCha20Rng::from_seed([7; 32])).unwrap());
+        assert_eq!(
+            shares,
+            encode(split_seed(&seed, 3, 5, &mut ChaCha20Rng::from_seed([7; 32])).unwrap())
+        );
+        assert_eq!(
+            shares[0],
+            "AwMBwHIEAExoAUzhC4ATv--_gvo4kCXd0IrxQT_KnO9IzNf1LEczAJxCgqZthAQ"
+        );
+
+        let decode = |indexes: &[usize]| -> Vec<Share> {
+            indexes
+                .iter()
+                .map(|&i| Share::decode(&shares[i]).unwrap())
+                .collect()
+        };
+        for indexes in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            assert_eq!(*combine_seed(&decode(&indexes)).unwrap(), seed);
+        }
+        assert_eq!(*combine_seed(&decode(&[0, 1, 2, 3, 4])).unwrap(), seed);
+        assert!(combine_seed(&decode(&[0, 1])).is_err());
+        assert!(combine_seed(&decode(&[0, 0, 1])).is_err());
+
+        let other = split_seed(&seed, 3, 5, &mut ChaCha20Rng::from_seed([8; 32])).unwrap();
+        let mut mixed = decode(&[0, 1, 2]);
+        mixed.push(other[3].clone());
+        assert!(combine_seed(&mixed).is_err());
+        let mut mixed = decode(&[0, 1]);
+        mixed.push(other[2].clone());
+        assert!(combine_seed(&mixed).is_err());
+        // the same rng gives the same split id and coefficients, so only the
+        // digest combined with the seed tells these apart. Share 3 would
+        // combine to the other seed, as its Lagrange weight with 1 and 2 is 1
+        let other_seed =
+            encode(split_seed(&[9; 32], 3, 5, &mut ChaCha20Rng::from_seed([7; 32])).unwrap());
+        let mut mixed = decode(&[0, 1]);
+        mixed.push(Share::decode(&other_seed[3]).unwrap());
+        assert!(combine_seed(&mixed).is_err());
+        // everything but the share values is independent of the seed
+        let header = |share: &str| {
+            base64::decode_config(share, base64::URL_SAFE_NO_PAD).unwrap()[..7].to_vec()
+        };
+        assert_eq!(header(&shares[2]), header(&other_seed[2]));
+
+        let mut typo = shares[0].clone().into_bytes();
+        typo[10] = if typo[10] == b'A' { b'B' } else { b'A' };
+        assert!(Share::decode(&String::from_utf8(typo).unwrap()).is_err());
+        assert!(split_seed(&seed, 1, 5, &mut ChaCha20Rng::from_seed([7; 32])).is_err());
+        assert!(split_seed(&seed, 6, 5, &mut ChaCha20Rng::from_seed([7; 32])).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shamir_master_seed_round_trip() -> Result<(), String> {
+        let mnemonic = format!("{} art", ["abandon"; 23].join(" "));
+        let master_seed = master_bundle_from_mnemonic(&mnemonic, "pass".to_string())
+            .await
+            .unwrap();
+        let shares = split_master_seed(
+            &master_seed,
+            "pass".to_string(),
+            2,
+            3,
+            &mut ChaCha20Rng::from_seed([7; 32]),
+        )
+        .await
+        .unwrap();
+        let combined = combine_master_seed(
+            &[shares[2].as_str(), shares[0].as_str()],
+            "new-pass".to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            *bundle_to_mnemonic(&combined, Some("new-pass".to_string()))
+                .await
+                .unwrap(),
+            mnemonic
+        );
+        Ok(())
+    }
+
     fn get_mock_config() -> Result<Config, String> {
         let email: String = "jack@holo.host".to_string();
         let password: String = "password".to_string();
         let registration_code: String = "registration-code".to_string();
         let derivation_path: String = "1".to_string();
-        let device_bundle = "k6VoY3NiMJGWonB3xBCZ0R47aR6ctMScaYsrOLwRzSAAAcQY58NsOmNCDbniGsLgUhj5UoHjBrapiiDGxDGAa5Wqzm0pVuXGN106iyMHRk4dOf0iGWj65oCeB8-ZYXJdeflsVDY-DOuJaadfPZQExCyCrWRldmljZV9udW1iZXIAq2dlbmVyYXRlX2J5r3F1aWNrc3RhcnQtdjIuMA".to_string();
+        let device_bundle = get_mock_device_bundle();
         let rev_key: [u8; 32] = [0 as u8; 32]; // TODO: Fill this in with something
         let (config, _) = Config::new(
             email,
@@ -33,6 +514,9 @@ mod tests {
         .unwrap();
         Ok(config)
     }
+    fn get_mock_device_bundle() -> String {
+        "k6VoY3NiMJGWonB3xBCZ0R47aR6ctMScaYsrOLwRzSAAAcQY58NsOmNCDbniGsLgUhj5UoHjBrapiiDGxDGAa5Wqzm0pVuXGN106iyMHRk4dOf0iGWj65oCeB8-ZYXJdeflsVDY-DOuJaadfPZQExCyCrWRldmljZV9udW1iZXIAq2dlbmVyYXRlX2J5r3F1aWNrc3RhcnQtdjIuMA".to_string()
+    }
     fn get_mock_pub_key() -> Result<VerifyingKey, String> {
         let device_pub_key: String = "To4PzBU8BcVghpjGjnYjLQnP_mkT9uBJ2v969Cs7-xw".to_string();
         Ok(
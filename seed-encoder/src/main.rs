@@ -1,11 +1,20 @@
 //! This binary is used for generating a encoded key from the
 //! seed, this is used by the `--load_ed25519_keypair_from_seed` in lair
 //!
+//! The keypair is encrypted with `--export-passphrase`, the legacy
+//! plaintext encoding is only written when `--plaintext` is passed.
+//!
+//! `--format` switches the output to PKCS#8 PEM, OpenSSH or JWK. The private
+//! key is written to stdout and the matching public key, if any, to stderr.
+//!
+//! For lair (>=v0.1) use `hpos-config-seed-bundle-explorer export-lair`.
 
 use anyhow::{Context, Result};
-use ed25519_dalek::*;
 use hpos_config_core::*;
-use hpos_config_seed_bundle_explorer::{encrypt_key, unlock};
+use hpos_config_seed_bundle_explorer::{
+    export::{export_key, KeyFormat},
+    holoport_key,
+};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -14,48 +23,41 @@ async fn main() -> Result<()> {
     #[derive(StructOpt)]
     struct Cli {
         #[structopt(long = "config-path")]
-        /// The path to the hpos-config file
-        config_path: PathBuf,
+        /// The path to the hpos-config file, defaults to $HPOS_CONFIG_PATH
+        /// or a well-known location
+        config_path: Option<PathBuf>,
         #[structopt(long = "password")]
         /// The password to unlock the device-bundle
         password: String,
+        #[structopt(long = "export-passphrase", required_unless = "plaintext")]
+        /// The passphrase used to encrypt the exported keypair
+        export_passphrase: Option<String>,
+        #[structopt(long = "plaintext", conflicts_with = "export_passphrase")]
+        /// Write the keypair unencrypted, as expected by lair (<v0.0.6)
+        plaintext: bool,
+        #[structopt(long = "format", default_value = "lair")]
+        /// Output format: lair, pem, openssh or jwk
+        format: KeyFormat,
     }
 
     let Cli {
         config_path,
         password,
+        export_passphrase,
+        format,
+        ..
     } = Cli::from_args();
-    use std::fs::File;
-    let config_file = File::open(&config_path).context(format!(
-        "failed to open file {}",
-        &config_path.to_string_lossy()
-    ))?;
-    match serde_json::from_reader(config_file)? {
-        Config::V1 { seed, .. } => {
-            let secret_key = SigningKey::from_bytes(&seed);
-            let public_key = secret_key.verifying_key();
-            println!("{}", encrypt_key(&secret_key, &public_key));
-        }
-        Config::V2 { device_bundle, .. } => {
-            // take in password
-            let secret = unlock(&device_bundle, Some(password))
-                .await
-                .context(format!(
-                    "unable to unlock the device bundle from {}",
-                    &config_path.to_string_lossy()
-                ))?;
-            println!("{}", encrypt_key(&secret, &secret.verifying_key()));
-        }
-        Config::V3 { device_bundle, .. } => {
-            // take in password
-            let secret = unlock(&device_bundle, Some(password))
-                .await
-                .context(format!(
-                    "unable to unlock the device bundle from {}",
-                    &config_path.to_string_lossy()
-                ))?;
-            println!("{}", encrypt_key(&secret, &secret.verifying_key()));
-        }
+    let (config, config_path) = load(config_path.as_deref())?;
+    let secret = holoport_key(&config, Some(password))
+        .await
+        .context(format!(
+            "unable to unlock the device bundle from {}",
+            &config_path.to_string_lossy()
+        ))?;
+    let exported = export_key(&secret, format, export_passphrase).await?;
+    println!("{}", exported.private_key.trim_end());
+    if let Some(public_key) = &exported.public_key {
+        eprintln!("{}", public_key.trim_end());
     }
 
     Ok(())